@@ -2,3 +2,51 @@ pub const CONFIG_SEED: &[u8] = b"config";
 pub const PRICES_SEED: &[u8] = b"prices";
 pub const GAMES_SEED: &[u8] = b"games";
 pub const VAULT_SEED: &[u8] = b"vault";
+pub const WAGER_MINT_SEED: &[u8] = b"wager_mint";
+pub const SOL_ESCROW_SEED: &[u8] = b"sol_escrow";
+pub const BALANCE_SEED: &[u8] = b"balance";
+pub const INTENTS_SEED: &[u8] = b"intents";
+pub const OPEN_GAMES_SEED: &[u8] = b"open_games";
+pub const FILLS_SEED: &[u8] = b"fills";
+pub const PROFILE_SEED: &[u8] = b"profile";
+pub const SEASON_SEED: &[u8] = b"season";
+pub const SEASON_SCORE_SEED: &[u8] = b"season_score";
+pub const REFERRAL_SEED: &[u8] = b"referral";
+pub const MARKET_ACCESS_SEED: &[u8] = b"market_access";
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+pub const SESSION_SEED: &[u8] = b"session";
+pub const SIDE_POOL_SEED: &[u8] = b"side_pool";
+pub const SIDE_BET_SEED: &[u8] = b"side_bet";
+
+pub const MAX_THRESHOLD_TIERS: usize = 8;
+/// Distance of the big outcome buckets from the start price, relative to the tier thresholds.
+pub const BIG_BUCKET_MULTIPLIER: u16 = 2;
+/// Opponent stake per unit of host stake in bucket games: even for a small bucket, which comes
+/// first about half the time, and long odds for a big one, reached only by a price gapping past
+/// the small boundary.
+pub const SMALL_BUCKET_ODDS: u64 = 1;
+pub const BIG_BUCKET_ODDS: u64 = 4;
+pub const MAX_QUEUED_INTENTS: usize = 64;
+/// Intents a player can have queued in a market at once.
+pub const MAX_INTENTS_PER_PLAYER: usize = 4;
+pub const MAX_OPEN_GAMES: usize = 64;
+pub const MAX_LEADERBOARD_SIZE: usize = 10;
+/// Upper bound of the protocol fee, 10% of a payout.
+pub const MAX_FEE_BPS: u16 = 1_000;
+pub const FEE_BPS_DENOMINATOR: u64 = 10_000;
+/// Upper bound of the referral share, so the referrers of both players fit in the fee.
+pub const MAX_REFERRAL_SHARE_BPS: u16 = 5_000;
+pub const INITIAL_RATING: u16 = 1_200;
+/// Largest rating change of a single game.
+pub const ELO_K_FACTOR: u16 = 32;
+/// Rating differences, winner less loser, from which the change of a game drops by one more
+/// point below `ELO_K_FACTOR`. The change is `K * (1 - expected)` rounded, with the winner's
+/// expected score `1 / (1 + 10^(-difference / 400))`, kept in integers.
+pub const ELO_CHANGE_STEPS: [i32; ELO_K_FACTOR as usize] = [
+    -719, -523, -428, -364, -314, -273, -237, -205, -176, -149, -124, -100, -77, -54, -32, -10, 11,
+    33, 55, 78, 101, 125, 150, 177, 206, 238, 274, 315, 365, 429, 524, 720,
+];
+/// Seconds an expiring game must have left for an opponent to join it.
+pub const MIN_JOIN_DURATION: i64 = 60;
+/// Seconds a rematch stays reserved for the previous opponent before anyone can join it.
+pub const REMATCH_WINDOW: i64 = 600;
@@ -30,8 +30,128 @@ pub enum Error {
     CannotJoinOwnGame,
     #[msg("Price moved too much since game creation")]
     PriceMovedTooMuch,
-    #[msg("Game is not finished yet - 5% threshold not reached")]
+    #[msg("Game is not finished yet - threshold not reached")]
     GameNotFinished,
     #[msg("Only winner can claim rewards")]
     SignerNotWinner,
+    #[msg("Range games require a duration above the minimum join window, directional games none")]
+    InvalidGameDuration,
+    #[msg("Game has expired or expires too soon to join")]
+    GameExpired,
+    #[msg("Price account does not belong to the game's market")]
+    InvalidPricesAccount,
+    #[msg("Relative games require a second, different market")]
+    InvalidOpponentMarket,
+    #[msg("Threshold tier does not exist")]
+    InvalidThresholdTier,
+    #[msg("Threshold tiers must be non-empty, bounded and have join below win threshold")]
+    InvalidThresholdTiers,
+    #[msg("Bet size is outside the limits of the mint")]
+    InvalidBetSize,
+    #[msg("Minimum bet size must be positive and not above the maximum")]
+    InvalidBetLimits,
+    #[msg("Mint is not enabled for new games")]
+    MintDisabled,
+    #[msg("Mint does not match the game")]
+    InvalidMint,
+    #[msg("Token accounts or SOL escrow of the mint are missing")]
+    MissingEscrowAccounts,
+    #[msg("Native SOL wagers are held by the SOL escrow")]
+    NativeMintVault,
+    #[msg("Vault balance does not cover the locked stakes")]
+    VaultInsolvent,
+    #[msg("Balance is too low")]
+    InsufficientBalance,
+    #[msg("Intent queue of the market is full")]
+    IntentQueueFull,
+    #[msg("Intent not found")]
+    IntentNotFound,
+    #[msg("No matching intents in the queue")]
+    NoMatchingIntents,
+    #[msg("No open game matches the requested side and stake")]
+    NoCompatibleGame,
+    #[msg("Game takes partial fills, use fill_game")]
+    PartialFillsOnly,
+    #[msg("Game does not take partial fills")]
+    PartialFillsNotAllowed,
+    #[msg("Fill amount must be positive and within the unfilled stake")]
+    InvalidFillAmount,
+    #[msg("No unclaimed fill of the player in this game")]
+    FillNotFound,
+    #[msg("Profile does not belong to the losing player")]
+    InvalidProfile,
+    #[msg("Protocol fee or referral share is above the maximum")]
+    InvalidFee,
+    #[msg("Season must end after it starts")]
+    InvalidSeasonTimes,
+    #[msg("Season is not running")]
+    SeasonNotActive,
+    #[msg("Season has not ended yet")]
+    SeasonNotEnded,
+    #[msg("Season is already closed")]
+    SeasonClosed,
+    #[msg("Season is not closed yet")]
+    SeasonNotClosed,
+    #[msg("Season score does not belong to the season or the player")]
+    InvalidSeasonScore,
+    #[msg("The season scores of both players are required while the season of the mint is active")]
+    MissingSeasonAccounts,
+    #[msg("No unclaimed prize of the player in this season")]
+    PrizeNotFound,
+    #[msg("Referrer is already set")]
+    ReferrerAlreadySet,
+    #[msg("Players cannot refer themselves")]
+    InvalidReferrer,
+    #[msg("Referral rewards of the player's referrer are required, and only for them")]
+    InvalidReferralRewards,
+    #[msg("No referral rewards to claim")]
+    NoReferralRewards,
+    #[msg("Player rating is outside the rating band of the game")]
+    RatingOutOfBand,
+    #[msg("Player has too many open games")]
+    PlayerOpenGamesLimit,
+    #[msg("Host and host profile are required to refund the anti-spam deposit")]
+    MissingHostAccounts,
+    #[msg("Market is restricted to holders of its gate mint and its allowlist")]
+    MarketAccessDenied,
+    #[msg("Signer is neither the player nor a live session key of the player")]
+    InvalidSession,
+    #[msg("Stake exceeds the spending cap of the session")]
+    SessionCapExceeded,
+    #[msg("Session keys can only stake from the player's balance")]
+    SessionRequiresBalance,
+    #[msg("Session must expire in the future")]
+    InvalidSessionExpiry,
+    #[msg("Only joined games without a result can be cancelled")]
+    CancelNotAllowed,
+    #[msg("Signer and counterparty must be the host and the opponent of the game")]
+    InvalidCounterparty,
+    #[msg("Only settled directional games can be rematched, by one of their players")]
+    RematchNotAllowed,
+    #[msg("Game is reserved for the previous opponent")]
+    GameReserved,
+    #[msg("Side bets are only taken on joined games whose result is not decided yet")]
+    SideBetsClosed,
+    #[msg("Players of a game cannot back a side of it")]
+    PlayerCannotBackSide,
+    #[msg("A backer can only back one side of a game")]
+    SideMismatch,
+    #[msg("Player has too many queued intents")]
+    PlayerIntentsLimit,
+    #[msg("Season is missing or not the running season of the mint")]
+    InvalidSeason,
+    #[msg("Mint already has a season that is not closed")]
+    SeasonAlreadyRunning,
+    #[msg("Session keys cannot create games while an anti-spam deposit is required")]
+    SessionSpamDeposit,
+    #[msg("Player has no standing proposal to cancel the game")]
+    NoCancelProposal,
+    #[msg("Bucket games require an outcome and the other modes none")]
+    InvalidOutcome,
+    #[msg("Matched players and their profiles are required to refund anti-spam deposits")]
+    MissingIntentAccounts,
+    #[msg("The same account is passed more than once")]
+    DuplicateAccount,
+    #[msg("Only the fills of a partial fill game the host won are settled as losses")]
+    FillNotLost,
 }
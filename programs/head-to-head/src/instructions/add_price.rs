@@ -4,6 +4,7 @@ use crate::utils::resize_account;
 use crate::{ constants::*, error::Error, state::* };
 
 #[derive(Accounts)]
+#[instruction(market: u16)]
 pub struct AddPrice<'info> {
     #[account(
         mut,
@@ -20,7 +21,7 @@ pub struct AddPrice<'info> {
 
     #[account(
         mut,
-        seeds = [PRICES_SEED],
+        seeds = [PRICES_SEED, &market.to_le_bytes()],
         bump,
     )]
     pub prices: Account<'info, Prices>,
@@ -30,7 +31,7 @@ pub struct AddPrice<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn add_price(ctx: Context<AddPrice>, price: u64) -> Result<()> {
+pub fn add_price(ctx: Context<AddPrice>, _market: u16, price: u64) -> Result<()> {
     let AddPrice { prices, .. } = ctx.accounts;
 
     require!(price > 0, Error::InvalidPrice);
@@ -39,9 +40,10 @@ pub fn add_price(ctx: Context<AddPrice>, price: u64) -> Result<()> {
         prices,
         &ctx.accounts.admin,
         &ctx.accounts.system_program,
-        std::mem::size_of::<u64>()
+        std::mem::size_of::<u64>() + std::mem::size_of::<i64>()
     )?;
     prices.prices.push(price);
+    prices.timestamps.push(Clock::get()?.unix_timestamp);
 
     Ok(())
 }
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, error::Error, state::{ AllowlistEntry, Config } };
+
+#[derive(Accounts)]
+#[instruction(market: u16, player: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(mut, address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        init,
+        seeds = [constants::ALLOWLIST_SEED, &market.to_le_bytes(), player.as_ref()],
+        bump,
+        payer = admin,
+        space = AllowlistEntry::len()
+    )]
+    pub allowlist_entry: Box<Account<'info, AllowlistEntry>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, market: u16, player: Pubkey) -> Result<()> {
+    let allowlist_entry = &mut ctx.accounts.allowlist_entry;
+    allowlist_entry.market = market;
+    allowlist_entry.player = player;
+
+    Ok(())
+}
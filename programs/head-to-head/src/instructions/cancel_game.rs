@@ -0,0 +1,188 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Balance, Config, Games, PlayerProfile, Prices, SolEscrow, WagerMint },
+    utils::Escrow,
+};
+
+#[derive(Accounts)]
+pub struct CancelGame<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// CHECK: Other player of the game, checked against the game. Receives native SOL refunds.
+    #[account(mut)]
+    pub counterparty: UncheckedAccount<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    /// Token accounts are omitted for native SOL wagers, which are paid from the SOL escrow.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        constraint = counterparty_token_account.mint == mint.key(),
+        constraint = counterparty_token_account.owner == counterparty.key()
+    )]
+    pub counterparty_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Credits the refund to the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    /// Credits the refund to the counterparty's balance instead of their wallet.
+    #[account(
+        mut,
+        has_one = mint,
+        constraint = counterparty_balance.player == counterparty.key()
+    )]
+    pub counterparty_balance: Option<Box<Account<'info, Balance>>>,
+
+    #[account(mut, seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    /// Price feed of the game market, a game the prices already decided can only be claimed.
+    pub prices: Box<Account<'info, Prices>>,
+
+    /// Price feed of the opponent market, required for relative games.
+    pub opponent_prices: Option<Box<Account<'info, Prices>>>,
+
+    #[account(mut, seeds = [constants::PROFILE_SEED, player.key().as_ref()], bump)]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    #[account(mut, seeds = [constants::PROFILE_SEED, counterparty.key().as_ref()], bump)]
+    pub counterparty_profile: Box<Account<'info, PlayerProfile>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Proposes to cancel a joined game, or agrees to the other player's proposal. Once both sides
+/// agree the game is cancelled and each player gets their stake back, as long as the prices have
+/// not decided it yet. The proposer can take their proposal back with `withdraw_cancel`. A draw
+/// is cancelled and refunded by either player alone.
+pub fn cancel_game(ctx: Context<CancelGame>, game_index: u32) -> Result<()> {
+    let CancelGame {
+        player,
+        counterparty,
+        config,
+        mint,
+        wager_mint,
+        player_token_account,
+        counterparty_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        counterparty_balance,
+        games_account,
+        prices,
+        opponent_prices,
+        profile,
+        counterparty_profile,
+        token_program,
+    } = ctx.accounts;
+
+    require!((game_index as usize) < games_account.games.len(), Error::GameNotFound);
+
+    let game = &mut games_account.games[game_index as usize];
+    require!(game.mint == mint.key(), Error::InvalidMint);
+    require!(!game.is_closed, Error::GameAlreadyClosed);
+
+    let opponent_prices = opponent_prices.as_deref().map(|prices| &**prices);
+    require!(game.validate_feeds(prices, opponent_prices), Error::InvalidPricesAccount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let is_undecided =
+        game.result.is_none() &&
+        game.check_result(prices, opponent_prices, config.threshold_decimals, now).is_none();
+    require!(game.opponent.is_some() && is_undecided, Error::CancelNotAllowed);
+    require!(
+        game.counterparty(player.key()) == Some(counterparty.key()),
+        Error::InvalidCounterparty
+    );
+
+    // Draws are refunded right away, other games once both sides agree
+    let is_draw = game.is_draw(prices, opponent_prices, config.threshold_decimals, now);
+    if !is_draw && !game.propose_cancel(player.key()) {
+        return Ok(());
+    }
+
+    let (player_refund, counterparty_refund) = if game.host == player.key() {
+        (game.amount, game.opponent_amount)
+    } else {
+        (game.opponent_amount, game.amount)
+    };
+    game.cancel();
+
+    profile.record_withdrawal(player_refund, true).ok_or(Error::ArithmeticOverflow)?;
+    counterparty_profile
+        .record_withdrawal(counterparty_refund, true)
+        .ok_or(Error::ArithmeticOverflow)?;
+
+    let refunds = [
+        (
+            player.to_account_info(),
+            player_token_account.as_deref(),
+            balance.as_deref_mut(),
+            player_refund,
+        ),
+        (
+            counterparty.to_account_info(),
+            counterparty_token_account.as_deref(),
+            counterparty_balance.as_deref_mut(),
+            counterparty_refund,
+        ),
+    ];
+
+    for (recipient, token_account, balance, refund) in refunds {
+        match balance {
+            Some(balance) => {
+                balance.credit(refund).ok_or(Error::ArithmeticOverflow)?;
+            }
+            None => {
+                let mut escrow = Escrow::new(
+                    mint,
+                    token_account,
+                    vault_account.as_deref_mut(),
+                    sol_escrow.as_deref(),
+                    token_program.as_ref()
+                )?;
+                escrow.pay_out(&recipient, refund, ctx.bumps.vault_account)?;
+
+                wager_mint.release(refund).ok_or(Error::ArithmeticOverflow)?;
+                escrow.check_solvency(wager_mint)?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Balance, IntentQueue, PlayerProfile, SolEscrow, WagerMint },
+    utils::Escrow,
+};
+
+#[derive(Accounts)]
+#[instruction(market: u16)]
+pub struct CancelIntent<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(mut, seeds = [constants::INTENTS_SEED, &market.to_le_bytes()], bump)]
+    pub intent_queue: Box<Account<'info, IntentQueue>>,
+
+    /// Token accounts are omitted for native SOL wagers, which are paid from the SOL escrow.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Credits the refund to the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn cancel_intent(ctx: Context<CancelIntent>, _market: u16, intent_id: u64) -> Result<()> {
+    let CancelIntent {
+        player,
+        mint,
+        wager_mint,
+        intent_queue,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        token_program,
+        profile,
+        ..
+    } = ctx.accounts;
+
+    let position = intent_queue.intents
+        .iter()
+        .position(|intent| intent.id == intent_id && intent.player == player.key())
+        .ok_or(Error::IntentNotFound)?;
+    let intent = intent_queue.intents.remove(position);
+    require!(intent.mint == mint.key(), Error::InvalidMint);
+
+    profile.init(player.key());
+    profile.record_withdrawal(intent.amount, true).ok_or(Error::ArithmeticOverflow)?;
+
+    if intent.spam_deposit > 0 {
+        profile.sub_lamports(intent.spam_deposit)?;
+        player.add_lamports(intent.spam_deposit)?;
+    }
+
+    match balance {
+        Some(balance) => {
+            balance.credit(intent.amount).ok_or(Error::ArithmeticOverflow)?;
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            escrow.pay_out(player, intent.amount, ctx.bumps.vault_account)?;
+
+            wager_mint.release(intent.amount).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+        }
+    }
+
+    Ok(())
+}
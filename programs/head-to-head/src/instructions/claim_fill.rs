@@ -0,0 +1,272 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{
+        Balance,
+        Config,
+        GameFills,
+        Games,
+        PlayerProfile,
+        Prices,
+        ReferralRewards,
+        Season,
+        SeasonScore,
+        SolEscrow,
+        WagerMint,
+    },
+    utils::{ elo_ratings, Escrow },
+};
+
+use super::{ credit_referrer, record_season };
+
+/// Pays a filler of a partial fill game their pro rata share of the pot when the host lost.
+#[derive(Accounts)]
+#[instruction(game_index: u32)]
+pub struct ClaimFill<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    /// Token accounts are omitted for native SOL wagers, which are paid from the SOL escrow.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Credits the payout to the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    #[account(mut, seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    #[account(mut, seeds = [constants::FILLS_SEED, &game_index.to_le_bytes()], bump)]
+    pub game_fills: Box<Account<'info, GameFills>>,
+
+    pub prices_account: Box<Account<'info, Prices>>,
+
+    pub opponent_prices_account: Option<Box<Account<'info, Prices>>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Profile of the host, who lost the game, to record the loss and update both ratings.
+    #[account(mut, seeds = [constants::PROFILE_SEED, opponent_profile.player.as_ref()], bump)]
+    pub opponent_profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Rewards of the filler's referrer, credited with a share of the protocol fee. Required when
+    /// the filler has a referrer.
+    #[account(mut)]
+    pub referral_rewards: Option<Box<Account<'info, ReferralRewards>>>,
+
+    /// Rewards of the host's referrer, read from `opponent_profile`. Required when they have one.
+    #[account(mut)]
+    pub opponent_referral_rewards: Option<Box<Account<'info, ReferralRewards>>>,
+
+    /// Season of the mint that is not closed yet, required until it is. While it is active the
+    /// season scores of the filler and the host are required too, the host's is created if missing.
+    #[account(mut)]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    #[account(mut, has_one = player)]
+    pub season_score: Option<Box<Account<'info, SeasonScore>>>,
+
+    /// Season score of the host, the filler's share of the host stake is taken off it. Created if
+    /// the host hasn't entered the season.
+    #[account(
+        init_if_needed,
+        seeds = [
+            constants::SEASON_SCORE_SEED,
+            &wager_mint.season.unwrap_or_default().to_le_bytes(),
+            opponent_profile.player.as_ref(),
+        ],
+        bump,
+        payer = payer,
+        space = SeasonScore::len()
+    )]
+    pub opponent_season_score: Option<Box<Account<'info, SeasonScore>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn claim_fill(ctx: Context<ClaimFill>, game_index: u32) -> Result<()> {
+    let ClaimFill {
+        player,
+        config,
+        mint,
+        wager_mint,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        games_account,
+        game_fills,
+        prices_account,
+        opponent_prices_account,
+        token_program,
+        profile,
+        opponent_profile,
+        referral_rewards,
+        opponent_referral_rewards,
+        season,
+        season_score,
+        opponent_season_score,
+        ..
+    } = ctx.accounts;
+
+    require!((game_index as usize) < games_account.games.len(), Error::GameNotFound);
+
+    let game = &mut games_account.games[game_index as usize];
+    require!(game.mint == mint.key(), Error::InvalidMint);
+    require!(game.partial_fills, Error::PartialFillsNotAllowed);
+
+    let now = Clock::get()?.unix_timestamp;
+
+    // The first filler to claim settles the game for everyone
+    let is_settled = game.result.is_some();
+    let result = match game.result {
+        Some(result) => result,
+        None => {
+            require!(!game.is_closed, Error::GameAlreadyClosed);
+            require!(game.is_started(), Error::GameNotStarted);
+
+            let opponent_prices = opponent_prices_account.as_deref().map(|prices| &**prices);
+            require!(
+                game.validate_feeds(prices_account, opponent_prices),
+                Error::InvalidPricesAccount
+            );
+
+            let result = game
+                .check_result(
+                    prices_account,
+                    opponent_prices,
+                    config.threshold_decimals,
+                    now
+                )
+                .ok_or(Error::GameNotFinished)?;
+
+            game.set_result(result);
+            game.close();
+            result
+        }
+    };
+    require!(game.host_prediction != result, Error::SignerNotWinner);
+
+    let fill = game_fills.fills
+        .iter_mut()
+        .find(|fill| fill.player == player.key() && !fill.is_claimed)
+        .ok_or(Error::FillNotFound)?;
+    fill.is_claimed = true;
+
+    let pot = game.fill_payout(fill.amount).ok_or(Error::ArithmeticOverflow)?;
+    let payout = wager_mint.take_fee(pot, config.fee_bps).ok_or(Error::ArithmeticOverflow)?;
+    let fee = pot - payout;
+    // The filler won their share of the matched host stake
+    let winnings = pot - fill.amount;
+
+    profile.init(player.key());
+    profile.record_win(payout).ok_or(Error::ArithmeticOverflow)?;
+
+    // The host's loss is recorded once, by the claim that settles the game, but every filler
+    // is rated against the host as if they played it alone
+    require!(Some(opponent_profile.player) == game.loser(), Error::InvalidProfile);
+    if !is_settled {
+        opponent_profile.record_loss().ok_or(Error::ArithmeticOverflow)?;
+    }
+    (profile.rating, opponent_profile.rating) = elo_ratings(
+        profile.rating,
+        opponent_profile.rating
+    );
+
+    // Both referrers get their share of the fee of every filler claim
+    let referral_shares = credit_referrer(
+        profile,
+        referral_rewards.as_deref_mut(),
+        wager_mint,
+        fee,
+        config.referral_share_bps
+    )?
+        .checked_add(
+            credit_referrer(
+                opponent_profile,
+                opponent_referral_rewards.as_deref_mut(),
+                wager_mint,
+                fee,
+                config.referral_share_bps
+            )?
+        )
+        .ok_or(Error::ArithmeticOverflow)?;
+
+    let kept_fee = fee.checked_sub(referral_shares).ok_or(Error::ArithmeticOverflow)?;
+    record_season(
+        wager_mint,
+        season.as_deref_mut().map(|season| &mut **season),
+        (
+            season_score.as_deref_mut().map(|score| &mut **score),
+            opponent_season_score.as_deref_mut().map(|score| &mut **score),
+        ),
+        game.loser(),
+        (winnings.saturating_sub(fee), winnings, kept_fee),
+        now
+    )?;
+
+    match balance {
+        Some(balance) => {
+            balance.credit(payout).ok_or(Error::ArithmeticOverflow)?;
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            escrow.pay_out(player, payout, ctx.bumps.vault_account)?;
+
+            wager_mint.release(payout).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,286 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{
+        Balance,
+        Config,
+        Games,
+        PlayerProfile,
+        Prices,
+        ReferralRewards,
+        Season,
+        SeasonScore,
+        SolEscrow,
+        WagerMint,
+    },
+    utils::{ elo_ratings, Escrow },
+};
+
+use super::{ credit_referrer, record_season, settle_game };
+
+/// Claims several games of one mint with a single payout. The `Prices` accounts of every
+/// market involved, the profiles of the losing players, the referral rewards of the player's
+/// and the losers' referrers and, while the season of the mint is active, the losers' season
+/// scores are passed as remaining accounts, in any order and each once. Games against losers who
+/// haven't entered the season are claimed with `claim_winnings`, which creates their scores.
+#[derive(Accounts)]
+pub struct ClaimMany<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    /// Token accounts are omitted for native SOL wagers, which are paid from the SOL escrow.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Credits the payout to the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    #[account(mut, seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Season of the mint that is not closed yet, required until it is. While it is active the
+    /// player's season score is required too.
+    #[account(mut)]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    #[account(mut, has_one = player)]
+    pub season_score: Option<Box<Account<'info, SeasonScore>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn claim_many<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>,
+    game_indices: Vec<u32>
+) -> Result<()> {
+    let ClaimMany {
+        player,
+        games_account,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        config,
+        token_program,
+        mint,
+        wager_mint,
+        profile,
+        season,
+        season_score,
+        ..
+    } = ctx.accounts;
+
+    require!(!game_indices.is_empty(), Error::GameNotFound);
+
+    // Copies of an account would overwrite each other's updates when written back
+    let accounts = ctx.remaining_accounts;
+    require!(
+        accounts
+            .iter()
+            .enumerate()
+            .all(|(index, account)| accounts[..index].iter().all(|other| other.key != account.key)),
+        Error::DuplicateAccount
+    );
+
+    let markets = remaining_accounts::<Prices>(ctx.remaining_accounts)?;
+    let mut loser_profiles = remaining_accounts::<PlayerProfile>(ctx.remaining_accounts)?;
+    let mut loser_scores = remaining_accounts::<SeasonScore>(ctx.remaining_accounts)?;
+    let mut rewards = remaining_accounts::<ReferralRewards>(ctx.remaining_accounts)?;
+    let find_prices = |market: u16| markets.iter().find(|prices| prices.market == market);
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut total: u64 = 0;
+    profile.init(player.key());
+
+    for game_index in game_indices {
+        let mut claim = || -> Result<(u64, Option<Pubkey>, u64)> {
+            let game = games_account.games
+                .get_mut(game_index as usize)
+                .ok_or(Error::GameNotFound)?;
+            require!(game.mint == mint.key(), Error::InvalidMint);
+
+            let prices = find_prices(game.market).ok_or(Error::InvalidPricesAccount)?;
+            let opponent_prices = match game.opponent_market {
+                Some(market) => Some(find_prices(market).ok_or(Error::InvalidPricesAccount)?),
+                None => None,
+            };
+
+            let pot = settle_game(
+                game,
+                player.key(),
+                prices,
+                opponent_prices.map(|prices| &**prices),
+                config.threshold_decimals,
+                now
+            )?;
+            let losing_stake = if game.host == player.key() {
+                game.opponent_amount
+            } else {
+                game.amount
+            };
+            Ok((pot, game.loser(), losing_stake))
+        };
+
+        let (pot, loser, losing_stake) = match claim() {
+            Ok(claimed) => claimed,
+            Err(error) => {
+                msg!("Claim of game {} failed", game_index);
+                return Err(error);
+            }
+        };
+        let payout = wager_mint.take_fee(pot, config.fee_bps).ok_or(Error::ArithmeticOverflow)?;
+        let fee = pot - payout;
+        total = total.checked_add(payout).ok_or(Error::ArithmeticOverflow)?;
+        profile.record_win(payout).ok_or(Error::ArithmeticOverflow)?;
+
+        let mut referral_shares = credit_referrer(
+            profile,
+            find_rewards(&mut rewards, profile.referrer, mint.key()),
+            wager_mint,
+            fee,
+            config.referral_share_bps
+        )?;
+
+        // Fillers of a partial fill game have no single profile to record the loss in or rate
+        if let Some(loser) = loser {
+            let loser_profile = loser_profiles
+                .iter_mut()
+                .find(|profile| profile.player == loser)
+                .ok_or(Error::InvalidProfile)?;
+            loser_profile.record_loss().ok_or(Error::ArithmeticOverflow)?;
+            (profile.rating, loser_profile.rating) = elo_ratings(
+                profile.rating,
+                loser_profile.rating
+            );
+
+            let share = credit_referrer(
+                loser_profile,
+                find_rewards(&mut rewards, loser_profile.referrer, mint.key()),
+                wager_mint,
+                fee,
+                config.referral_share_bps
+            )?;
+            referral_shares = referral_shares.checked_add(share).ok_or(Error::ArithmeticOverflow)?;
+        }
+
+        let kept_fee = fee.checked_sub(referral_shares).ok_or(Error::ArithmeticOverflow)?;
+        let loser_score = loser.and_then(|loser| {
+            loser_scores.iter_mut().find(|score| score.player == loser)
+        });
+        record_season(
+            wager_mint,
+            season.as_deref_mut().map(|season| &mut **season),
+            (
+                season_score.as_deref_mut().map(|score| &mut **score),
+                loser_score.map(|score| &mut **score),
+            ),
+            loser,
+            (losing_stake.saturating_sub(fee), losing_stake, kept_fee),
+            now
+        )?;
+    }
+
+    for loser_profile in &loser_profiles {
+        loser_profile.exit(ctx.program_id)?;
+    }
+    for loser_score in &loser_scores {
+        loser_score.exit(ctx.program_id)?;
+    }
+    for rewards in &rewards {
+        rewards.exit(ctx.program_id)?;
+    }
+
+    match balance {
+        Some(balance) => {
+            balance.credit(total).ok_or(Error::ArithmeticOverflow)?;
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            escrow.pay_out(player, total, ctx.bumps.vault_account)?;
+
+            wager_mint.release(total).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Rewards account of `referrer` in `mint` among the remaining accounts.
+fn find_rewards<'a, 'info>(
+    rewards: &'a mut [Account<'info, ReferralRewards>],
+    referrer: Option<Pubkey>,
+    mint: Pubkey
+) -> Option<&'a mut Account<'info, ReferralRewards>> {
+    rewards
+        .iter_mut()
+        .find(|rewards| Some(rewards.referrer) == referrer && rewards.mint == mint)
+}
+
+/// Remaining accounts of type `T`, told apart from the others by their discriminator.
+fn remaining_accounts<'info, T>(
+    accounts: &'info [AccountInfo<'info>]
+) -> Result<Vec<Account<'info, T>>>
+    where T: AccountSerialize + AccountDeserialize + Discriminator + Owner + Clone
+{
+    accounts
+        .iter()
+        .filter(|account| {
+            account.try_borrow_data().is_ok_and(|data| data.starts_with(&T::DISCRIMINATOR))
+        })
+        .map(Account::try_from)
+        .collect()
+}
@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Balance, ReferralRewards, SolEscrow, WagerMint },
+    utils::Escrow,
+};
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::REFERRAL_SEED, player.key().as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub referral_rewards: Box<Account<'info, ReferralRewards>>,
+
+    /// Token accounts are omitted for native SOL wagers, which are paid from the SOL escrow.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Credits the rewards to the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+    let ClaimReferralRewards {
+        player,
+        mint,
+        wager_mint,
+        referral_rewards,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        token_program,
+    } = ctx.accounts;
+
+    let amount = referral_rewards.amount;
+    require!(amount > 0, Error::NoReferralRewards);
+    referral_rewards.amount = 0;
+
+    match balance {
+        Some(balance) => {
+            balance.credit(amount).ok_or(Error::ArithmeticOverflow)?;
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            escrow.pay_out(player, amount, ctx.bumps.vault_account)?;
+
+            wager_mint.release(amount).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+        }
+    }
+
+    Ok(())
+}
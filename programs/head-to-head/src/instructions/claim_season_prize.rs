@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Balance, Season, SolEscrow, WagerMint },
+    utils::Escrow,
+};
+
+#[derive(Accounts)]
+#[instruction(season_id: u32)]
+pub struct ClaimSeasonPrize<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::SEASON_SEED, &season_id.to_le_bytes()],
+        bump,
+        has_one = mint
+    )]
+    pub season: Box<Account<'info, Season>>,
+
+    /// Token accounts are omitted for native SOL wagers, which are paid from the SOL escrow.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Credits the prize to the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+pub fn claim_season_prize(ctx: Context<ClaimSeasonPrize>, _season_id: u32) -> Result<()> {
+    let ClaimSeasonPrize {
+        player,
+        mint,
+        wager_mint,
+        season,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        token_program,
+    } = ctx.accounts;
+
+    require!(season.is_closed, Error::SeasonNotClosed);
+
+    let entry = season.leaderboard
+        .iter_mut()
+        .find(|entry| entry.player == player.key() && entry.prize > 0 && !entry.is_claimed)
+        .ok_or(Error::PrizeNotFound)?;
+    entry.is_claimed = true;
+    let prize = entry.prize;
+
+    match balance {
+        Some(balance) => {
+            balance.credit(prize).ok_or(Error::ArithmeticOverflow)?;
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            escrow.pay_out(player, prize, ctx.bumps.vault_account)?;
+
+            wager_mint.release(prize).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Balance, Config, Games, Prices, SideBet, SidePool, SolEscrow, WagerMint },
+    utils::Escrow,
+};
+
+#[derive(Accounts)]
+#[instruction(game_index: u32)]
+pub struct ClaimSideBet<'info> {
+    #[account(mut)]
+    pub backer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    pub prices: Box<Account<'info, Prices>>,
+
+    /// Price feed of the opponent market, required for relative games.
+    pub opponent_prices: Option<Box<Account<'info, Prices>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::SIDE_POOL_SEED, &game_index.to_le_bytes()],
+        bump,
+        has_one = mint
+    )]
+    pub side_pool: Box<Account<'info, SidePool>>,
+
+    #[account(
+        mut,
+        close = backer,
+        seeds = [constants::SIDE_BET_SEED, &game_index.to_le_bytes(), backer.key().as_ref()],
+        bump
+    )]
+    pub side_bet: Box<Account<'info, SideBet>>,
+
+    /// Token accounts are omitted for native SOL wagers, which are paid from the SOL escrow.
+    #[account(
+        mut,
+        constraint = backer_token_account.mint == mint.key(),
+        constraint = backer_token_account.owner == backer.key()
+    )]
+    pub backer_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Credits the payout to the backer's balance instead of their wallet.
+    #[account(mut, has_one = mint, constraint = balance.player == backer.key())]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Pays out a side bet once its game is decided, or refunds it when the game was cancelled.
+/// The first claim fixes the outcome of the pool, either from the settled game or from the
+/// prices when nobody claimed the game yet. Losing bets are closed without a payout.
+pub fn claim_side_bet(ctx: Context<ClaimSideBet>, game_index: u32) -> Result<()> {
+    let ClaimSideBet {
+        backer,
+        config,
+        mint,
+        wager_mint,
+        games_account,
+        prices,
+        opponent_prices,
+        side_pool,
+        side_bet,
+        backer_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        token_program,
+    } = ctx.accounts;
+
+    require!((game_index as usize) < games_account.games.len(), Error::GameNotFound);
+
+    let game = &games_account.games[game_index as usize];
+
+    if side_pool.host_won.is_none() && !game.is_cancelled {
+        let opponent_prices = opponent_prices.as_deref().map(|prices| &**prices);
+        require!(game.validate_feeds(prices, opponent_prices), Error::InvalidPricesAccount);
+
+        let now = Clock::get()?.unix_timestamp;
+        // Side bets on a draw are refunded like on a cancelled game
+        if !game.is_draw(prices, opponent_prices, config.threshold_decimals, now) {
+            let result = game.result
+                .or_else(|| {
+                    game.check_result(prices, opponent_prices, config.threshold_decimals, now)
+                })
+                .ok_or(Error::GameNotFinished)?;
+            side_pool.host_won = Some(result == game.host_prediction);
+        }
+    }
+
+    let payout = side_pool
+        .payout(side_bet.backs_host, side_bet.amount)
+        .ok_or(Error::ArithmeticOverflow)?;
+    if payout == 0 {
+        return Ok(());
+    }
+
+    // Winnings pay the protocol fee like game payouts, refunds and unopposed stakes are returned
+    // in full
+    let payout = if side_pool.has_winnings(side_bet.backs_host) {
+        wager_mint.take_fee(payout, config.fee_bps).ok_or(Error::ArithmeticOverflow)?
+    } else {
+        payout
+    };
+
+    match balance {
+        Some(balance) => {
+            balance.credit(payout).ok_or(Error::ArithmeticOverflow)?;
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                backer_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            escrow.pay_out(backer, payout, ctx.bumps.vault_account)?;
+
+            wager_mint.release(payout).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,87 +1,383 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{ Token, TokenAccount };
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
 
 use crate::{
-    constants::{ self, VAULT_SEED },
+    constants,
     error::Error,
-    state::{ Config, Games, Prices },
-    utils::transfer_tokens,
+    state::{
+        Balance,
+        Config,
+        Game,
+        Games,
+        PlayerProfile,
+        Prices,
+        ReferralRewards,
+        Season,
+        SeasonScore,
+        Session,
+        SolEscrow,
+        WagerMint,
+    },
+    utils::{ authorize_session, elo_ratings, Escrow },
 };
 
 #[derive(Accounts)]
 pub struct ClaimWinnings<'info> {
+    /// Player's wallet, or a session key of the player passed with its `session`.
     #[account(mut)]
-    pub player: Signer<'info>,
+    pub authority: Signer<'info>,
+
+    /// CHECK: Player the instruction acts for, checked against `authority` and `session`.
+    #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub session: Option<Box<Account<'info, Session>>>,
 
     #[account(seeds = [constants::CONFIG_SEED], bump)]
     pub config: Box<Account<'info, Config>>,
 
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
     #[account(
         mut,
-        constraint = player_token_account.mint == config.mint,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    /// Token accounts are omitted for native SOL wagers, which are paid from the SOL escrow.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
         constraint = player_token_account.owner == player.key()
     )]
-    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
         mut,
-        seeds = [constants::VAULT_SEED],
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
         bump,
-        token::mint = config.mint,
+        token::mint = mint,
     )]
-    pub vault_account: Box<Account<'info, TokenAccount>>,
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Credits the payout to the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
 
     #[account(mut, seeds = [constants::GAMES_SEED], bump)]
     pub games_account: Box<Account<'info, Games>>,
 
-    #[account(seeds = [constants::PRICES_SEED], bump)]
     pub prices_account: Box<Account<'info, Prices>>,
 
-    pub token_program: Program<'info, Token>,
+    pub opponent_prices_account: Option<Box<Account<'info, Prices>>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Profile of the losing player, to record the loss and update both ratings. Required unless
+    /// the fillers of a partial fill game lost.
+    #[account(mut, seeds = [constants::PROFILE_SEED, opponent_profile.player.as_ref()], bump)]
+    pub opponent_profile: Option<Box<Account<'info, PlayerProfile>>>,
+
+    /// Rewards of the winner's referrer, credited with a share of the protocol fee. Required when
+    /// the winner has a referrer.
+    #[account(mut)]
+    pub referral_rewards: Option<Box<Account<'info, ReferralRewards>>>,
+
+    /// Rewards of the loser's referrer, read from `opponent_profile`. Required when they have one.
+    #[account(mut)]
+    pub opponent_referral_rewards: Option<Box<Account<'info, ReferralRewards>>>,
+
+    /// Season of the mint that is not closed yet, required until it is. While it is active the
+    /// season scores of both players are required too, the loser's is created if missing.
+    #[account(mut)]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    #[account(mut, has_one = player)]
+    pub season_score: Option<Box<Account<'info, SeasonScore>>>,
+
+    /// Season score of the losing player, the loss is taken off their net winnings. Created if
+    /// they haven't entered the season.
+    #[account(
+        init_if_needed,
+        seeds = [
+            constants::SEASON_SCORE_SEED,
+            &wager_mint.season.unwrap_or_default().to_le_bytes(),
+            opponent_profile.as_ref().map(|profile| profile.player).unwrap_or_default().as_ref(),
+        ],
+        bump,
+        payer = payer,
+        space = SeasonScore::len()
+    )]
+    pub opponent_season_score: Option<Box<Account<'info, SeasonScore>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn claim_winnings(ctx: Context<ClaimWinnings>, game_index: u32) -> Result<()> {
     let ClaimWinnings {
+        authority,
+        session,
         player,
         games_account,
         prices_account,
+        opponent_prices_account,
         player_token_account,
         vault_account,
+        sol_escrow,
+        balance,
         config,
         token_program,
+        mint,
+        wager_mint,
+        profile,
+        opponent_profile,
+        season,
+        season_score,
+        opponent_season_score,
+        referral_rewards,
+        opponent_referral_rewards,
         ..
     } = ctx.accounts;
 
     require!((game_index as usize) < games_account.games.len(), Error::GameNotFound);
 
     let game = &mut games_account.games[game_index as usize];
-    require!(!game.is_closed, Error::GameAlreadyClosed);
-    require!(game.opponent.is_some(), Error::GameNotStarted);
+    require!(game.mint == mint.key(), Error::InvalidMint);
+
+    let now = Clock::get()?.unix_timestamp;
+    authorize_session(
+        player,
+        authority,
+        session.as_deref_mut().map(|session| &mut **session),
+        0,
+        now
+    )?;
+
+    let pot = settle_game(
+        game,
+        player.key(),
+        prices_account,
+        opponent_prices_account.as_deref().map(|prices| &**prices),
+        config.threshold_decimals,
+        now
+    )?;
+    let payout = wager_mint.take_fee(pot, config.fee_bps).ok_or(Error::ArithmeticOverflow)?;
+    let fee = pot - payout;
 
-    let result = game.check_price_fluctuation(
-        &prices_account.prices,
-        config.win_threshold_percent,
-        prices_account.decimals,
-        config.threshold_decimals
+    profile.init(player.key());
+    profile.record_win(payout).ok_or(Error::ArithmeticOverflow)?;
+
+    let mut referral_shares = credit_referrer(
+        profile,
+        referral_rewards.as_deref_mut(),
+        wager_mint,
+        fee,
+        config.referral_share_bps
+    )?;
+
+    require!(
+        opponent_profile.as_ref().map(|opponent| opponent.player) == game.loser(),
+        Error::InvalidProfile
     );
+    if let Some(opponent_profile) = opponent_profile {
+        opponent_profile.record_loss().ok_or(Error::ArithmeticOverflow)?;
+        (profile.rating, opponent_profile.rating) = elo_ratings(
+            profile.rating,
+            opponent_profile.rating
+        );
+
+        let share = credit_referrer(
+            opponent_profile,
+            opponent_referral_rewards.as_deref_mut(),
+            wager_mint,
+            fee,
+            config.referral_share_bps
+        )?;
+        referral_shares = referral_shares.checked_add(share).ok_or(Error::ArithmeticOverflow)?;
+    }
+
+    // Net winnings are the loser's stake, less the fee taken from the pot
+    let losing_stake = if game.host == player.key() { game.opponent_amount } else { game.amount };
+    let kept_fee = fee.checked_sub(referral_shares).ok_or(Error::ArithmeticOverflow)?;
+    record_season(
+        wager_mint,
+        season.as_deref_mut().map(|season| &mut **season),
+        (
+            season_score.as_deref_mut().map(|score| &mut **score),
+            opponent_season_score.as_deref_mut().map(|score| &mut **score),
+        ),
+        game.loser(),
+        (losing_stake.saturating_sub(fee), losing_stake, kept_fee),
+        now
+    )?;
+
+    match balance {
+        Some(balance) => {
+            balance.credit(payout).ok_or(Error::ArithmeticOverflow)?;
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            escrow.pay_out(player, payout, ctx.bumps.vault_account)?;
+
+            wager_mint.release(payout).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Credits the referrer of `profile` with their share of `fee` and returns it. The rewards account
+/// of the referrer is required whenever the player has one, and rejected otherwise.
+pub(crate) fn credit_referrer(
+    profile: &PlayerProfile,
+    referral_rewards: Option<&mut Account<ReferralRewards>>,
+    wager_mint: &mut WagerMint,
+    fee: u64,
+    share_bps: u16
+) -> Result<u64> {
+    let referral_rewards = match (profile.referrer, referral_rewards) {
+        (None, None) => {
+            return Ok(0);
+        }
+        (Some(referrer), Some(referral_rewards)) if
+            referral_rewards.referrer == referrer &&
+            referral_rewards.mint == wager_mint.mint
+        => referral_rewards,
+        _ => {
+            return err!(Error::InvalidReferralRewards);
+        }
+    };
+
+    let share = wager_mint.share_fee(fee, share_bps).ok_or(Error::ArithmeticOverflow)?;
+    referral_rewards.amount = referral_rewards.amount
+        .checked_add(share)
+        .ok_or(Error::ArithmeticOverflow)?;
+
+    Ok(share)
+}
+
+/// Counts a settled game towards the season of the mint while it is active: the winner's net
+/// winnings go up by `winnings`, the loser's down by `loss` and the protocol `fee` kept funds
+/// the prize pool. `scores` are the season scores of the winner and the loser.
+pub(crate) fn record_season(
+    wager_mint: &WagerMint,
+    season: Option<&mut Season>,
+    scores: (Option<&mut SeasonScore>, Option<&mut SeasonScore>),
+    loser: Option<Pubkey>,
+    (winnings, loss, fee): (u64, u64, u64),
+    now: i64
+) -> Result<()> {
+    let season = match (wager_mint.season, season) {
+        (Some(season_id), Some(season)) if season.id == season_id => season,
+        (None, None) => {
+            return Ok(());
+        }
+        _ => {
+            return err!(Error::InvalidSeason);
+        }
+    };
+
+    let (score, loser_score) = match (season.is_active(now), scores) {
+        (true, (Some(score), loser_score)) => (score, loser_score),
+        (false, (None, None)) => {
+            return Ok(());
+        }
+        (true, (None, _)) => {
+            return err!(Error::MissingSeasonAccounts);
+        }
+        (false, _) => {
+            return err!(Error::SeasonNotActive);
+        }
+    };
+
+    require!(score.season == season.id, Error::InvalidSeasonScore);
+    score.record_win(winnings).ok_or(Error::ArithmeticOverflow)?;
+    season.record(score.player, score.net_winnings);
+
+    // Fillers of a partial fill game that lost have no single score to take the loss off
+    match (loser, loser_score) {
+        (Some(loser), Some(loser_score)) => {
+            loser_score.init(season.id, loser);
+            require!(
+                loser_score.season == season.id && loser_score.player == loser,
+                Error::InvalidSeasonScore
+            );
+            loser_score.record_loss(loss);
+            season.record(loser, loser_score.net_winnings);
+        }
+        (None, None) => {}
+        (Some(_), None) => {
+            return err!(Error::MissingSeasonAccounts);
+        }
+        (None, Some(_)) => {
+            return err!(Error::InvalidSeasonScore);
+        }
+    }
+
+    season.prize_pool = season.prize_pool.checked_add(fee).ok_or(Error::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+/// Checks that `player` won `game`, closes it and returns the pot to pay out. Fillers of partial
+/// fill games claim through `claim_fill` instead.
+pub(crate) fn settle_game(
+    game: &mut Game,
+    player: Pubkey,
+    prices: &Prices,
+    opponent_prices: Option<&Prices>,
+    threshold_decimals: u8,
+    now: i64
+) -> Result<u64> {
+    require!(!game.is_closed, Error::GameAlreadyClosed);
+    require!(game.is_started(), Error::GameNotStarted);
+    require!(game.validate_feeds(prices, opponent_prices), Error::InvalidPricesAccount);
+
+    let result = game.check_result(prices, opponent_prices, threshold_decimals, now);
 
     require!(result.is_some(), Error::GameNotFinished);
     let is_host_winner = game.host_prediction == result.unwrap();
-    let winner_pubkey = if is_host_winner { game.host } else { game.opponent.unwrap() };
-    require!(player.key() == winner_pubkey, Error::SignerNotWinner);
-
-    transfer_tokens(
-        vault_account.to_account_info(),
-        player_token_account.to_account_info(),
-        vault_account.to_account_info(),
-        game.amount * 2,
-        token_program.to_account_info(),
-        Some(&[&[VAULT_SEED, &[ctx.bumps.vault_account]]])
-    )?;
+    let winner_pubkey = if is_host_winner { Some(game.host) } else { game.opponent };
+    require!(winner_pubkey == Some(player), Error::SignerNotWinner);
+
+    // The winning host of a partial fill game also takes back the unmatched stake
+    let remainder = if game.partial_fills {
+        game.freeze_fills().ok_or(Error::ArithmeticOverflow)?
+    } else {
+        0
+    };
+    let pot = game
+        .pot()
+        .and_then(|pot| pot.checked_add(remainder))
+        .ok_or(Error::ArithmeticOverflow)?;
 
     game.set_result(result.unwrap());
     game.close();
 
-    Ok(())
+    Ok(pot)
 }
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, error::Error, state::{ Config, Season, WagerMint } };
+
+#[derive(Accounts)]
+#[instruction(season_id: u32)]
+pub struct CloseSeason<'info> {
+    #[account(address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut, seeds = [constants::SEASON_SEED, &season_id.to_le_bytes()], bump)]
+    pub season: Box<Account<'info, Season>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, season.mint.as_ref()],
+        bump
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+}
+
+/// Closes an ended season and splits its prize pool between the leaderboard, to be collected with
+/// `claim_season_prize`. Fees left undistributed roll into the next season.
+pub fn close_season(ctx: Context<CloseSeason>, _season_id: u32) -> Result<()> {
+    let CloseSeason { season, wager_mint, .. } = ctx.accounts;
+
+    require!(!season.is_closed, Error::SeasonClosed);
+    require!(Clock::get()?.unix_timestamp >= season.end_time, Error::SeasonNotEnded);
+
+    let prize_pool = season.prize_pool;
+    let distributed = season.distribute(prize_pool).ok_or(Error::ArithmeticOverflow)?;
+    wager_mint.fees = wager_mint.fees.checked_sub(distributed).ok_or(Error::ArithmeticOverflow)?;
+    wager_mint.season = None;
+    season.is_closed = true;
+
+    Ok(())
+}
@@ -1,75 +1,310 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{ Token, TokenAccount };
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
 use crate::{
-    constants,
-    state::{ Config, Game, Games, Prices },
-    utils::{ resize_account, transfer_tokens },
+    constants::{ self, MIN_JOIN_DURATION },
+    error::Error,
+    state::{
+        AllowlistEntry,
+        Balance,
+        Config,
+        Game,
+        GameMode,
+        Games,
+        OpenGames,
+        Outcome,
+        PlayerProfile,
+        Prices,
+        Session,
+        SolEscrow,
+        WagerMint,
+    },
+    utils::{ authorize_session, check_market_access, load_market_access, resize_account, Escrow },
 };
 
+#[derive(Clone, AnchorDeserialize, AnchorSerialize)]
+pub struct CreateGameArgs {
+    /// Ignored for bucket games, whose host backs `outcome`.
+    pub prediction: bool,
+    pub mode: GameMode,
+    /// Seconds until the game expires, above `MIN_JOIN_DURATION`. Required for range games,
+    /// optional for relative games and must be `None` for directional games.
+    pub duration: Option<i64>,
+    pub market: u16,
+    /// Index into `Config.threshold_tiers`.
+    pub tier: u8,
+    /// Base stake, must be within the limits of the wager mint.
+    pub bet_size: u64,
+    /// Lets several opponents fill the opponent stake in portions through `fill_game`.
+    pub partial_fills: bool,
+    /// Largest rating difference to the host an opponent may have, `None` for anyone.
+    pub rating_band: Option<u16>,
+    /// Bucket the host backs, required for bucket games and `None` otherwise.
+    pub outcome: Option<Outcome>,
+}
+
 #[derive(Accounts)]
+#[instruction(args: CreateGameArgs)]
 pub struct CreateGame<'info> {
+    /// Player's wallet, or a session key of the player passed with its `session`.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Player the instruction acts for, checked against `authority` and `session`.
+    #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
     #[account(mut)]
-    pub player: Signer<'info>,
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub session: Option<Box<Account<'info, Session>>>,
 
     #[account(seeds = [constants::CONFIG_SEED], bump)]
     pub config: Box<Account<'info, Config>>,
 
-    #[account(seeds = [constants::PRICES_SEED], bump)]
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(seeds = [constants::PRICES_SEED, &args.market.to_le_bytes()], bump)]
     pub prices_account: Box<Account<'info, Prices>>,
 
+    /// Market backed by the opponent in relative games.
+    pub opponent_prices_account: Option<Box<Account<'info, Prices>>>,
+
+    /// Token accounts are omitted for native SOL wagers, which go to the SOL escrow instead.
     #[account(
         mut,
-        constraint = player_token_account.mint == config.mint,
+        constraint = player_token_account.mint == mint.key(),
         constraint = player_token_account.owner == player.key()
     )]
-    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
         mut,
-        seeds = [constants::VAULT_SEED],
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
         bump,
-        token::mint = config.mint,
+        token::mint = mint,
         token::authority = vault_account
     )]
-    pub vault_account: Box<Account<'info, TokenAccount>>,
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Pays the stake from the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
 
     #[account(mut, seeds = [constants::GAMES_SEED], bump)]
     pub games_account: Box<Account<'info, Games>>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        init_if_needed,
+        seeds = [constants::OPEN_GAMES_SEED, &args.market.to_le_bytes()],
+        bump,
+        payer = payer,
+        space = OpenGames::len()
+    )]
+    pub open_games: Box<Account<'info, OpenGames>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// CHECK: `MarketAccess` of the market, uninitialized for markets open to everyone.
+    #[account(seeds = [constants::MARKET_ACCESS_SEED, &args.market.to_le_bytes()], bump)]
+    pub market_access: UncheckedAccount<'info>,
+
+    /// Token account of the gate mint, for holders in gated markets.
+    pub access_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub allowlist_entry: Option<Box<Account<'info, AllowlistEntry>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn create_game(ctx: Context<CreateGame>, prediction: bool) -> Result<()> {
+pub fn create_game(ctx: Context<CreateGame>, args: CreateGameArgs) -> Result<()> {
     let CreateGame {
+        authority,
+        payer,
+        session,
         config,
+        wager_mint,
         prices_account: prices,
+        opponent_prices_account: opponent_prices,
         games_account: games,
+        open_games,
         player,
         player_token_account,
         system_program,
         vault_account,
+        sol_escrow,
+        balance,
+        mint,
+        token_program,
+        profile,
+        market_access,
+        access_token_account,
+        allowlist_entry,
         ..
     } = ctx.accounts;
 
-    let new_game = Game::new(
+    let CreateGameArgs {
+        prediction,
+        mode,
+        duration,
+        market,
+        tier,
+        bet_size,
+        partial_fills,
+        rating_band,
+        outcome,
+    } = args;
+
+    require!(wager_mint.is_enabled, Error::MintDisabled);
+    require!(wager_mint.accepts(bet_size), Error::InvalidBetSize);
+
+    // Markets with a `MarketAccess` gate are reserved to its holders and allowlist
+    let is_gated = match load_market_access(market_access)? {
+        Some(market_access) => {
+            check_market_access(
+                &market_access,
+                player.key(),
+                access_token_account.as_deref(),
+                allowlist_entry.as_deref()
+            )?;
+            true
+        }
+        None => false,
+    };
+    require!(
+        config.max_open_games_per_player == 0 ||
+            games.open_games_of(player.key()) < (config.max_open_games_per_player as usize),
+        Error::PlayerOpenGamesLimit
+    );
+
+    let thresholds = config.threshold_tier(tier).ok_or(Error::InvalidThresholdTier)?;
+
+    let expires_at = match (mode, duration) {
+        (GameMode::Directional | GameMode::Relative | GameMode::Buckets, None) => None,
+        (GameMode::Range | GameMode::Relative, Some(duration)) if duration > MIN_JOIN_DURATION => {
+            let now = Clock::get()?.unix_timestamp;
+            Some(now.checked_add(duration).ok_or(Error::ArithmeticOverflow)?)
+        }
+        _ => {
+            return err!(Error::InvalidGameDuration);
+        }
+    };
+
+    let mut new_game = Game::new(
         player.key(),
         prediction,
-        config.bet_size,
-        (prices.prices.len() as u32) - 1
+        mint.key(),
+        market,
+        prices.last_index(),
+        mode,
+        expires_at
     );
+    new_game.set_tier(tier, thresholds);
+    new_game.duration = duration;
+    new_game.is_gated = is_gated;
 
-    resize_account(games, player, system_program, std::mem::size_of::<Game>())?;
-    games.games.push(new_game);
+    match (mode, outcome) {
+        (GameMode::Buckets, Some(outcome)) => new_game.set_outcome(outcome),
+        (GameMode::Directional | GameMode::Range | GameMode::Relative, None) => {}
+        _ => {
+            return err!(Error::InvalidOutcome);
+        }
+    }
+
+    match (mode, opponent_prices) {
+        (GameMode::Relative, Some(opponent_prices)) if opponent_prices.market != market => {
+            new_game.set_opponent_feed(opponent_prices.market, opponent_prices.last_index());
+        }
+        (GameMode::Directional | GameMode::Range | GameMode::Buckets, None) => {}
+        _ => {
+            return err!(Error::InvalidOpponentMarket);
+        }
+    }
+
+    let (host_stake, opponent_stake) = new_game
+        .stakes(bet_size)
+        .ok_or(Error::ArithmeticOverflow)?;
 
-    transfer_tokens(
-        player_token_account.to_account_info(),
-        vault_account.to_account_info(),
-        player.to_account_info(),
-        config.bet_size,
-        ctx.accounts.token_program.to_account_info(),
-        None
+    let is_session = authorize_session(
+        player,
+        authority,
+        session.as_deref_mut().map(|session| &mut **session),
+        host_stake,
+        Clock::get()?.unix_timestamp
     )?;
+    require!(!is_session || balance.is_some(), Error::SessionRequiresBalance);
+    // The deposit is refunded to the player, so a session key may not put it up for them
+    require!(!is_session || config.spam_deposit == 0, Error::SessionSpamDeposit);
+
+    let received = match balance {
+        Some(balance) => {
+            balance.debit(host_stake).ok_or(Error::InsufficientBalance)?;
+            host_stake
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            let received = escrow.deposit(authority, host_stake, system_program)?;
+
+            wager_mint.lock(received).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+            received
+        }
+    };
+    new_game.set_stakes(received, opponent_stake);
+
+    profile.init(player.key());
+    profile.record_wager(received).ok_or(Error::ArithmeticOverflow)?;
+    new_game.set_rating_band(profile.rating, rating_band.unwrap_or(0));
+
+    // The anti-spam deposit waits in the host's profile until the game is joined or withdrawn
+    if config.spam_deposit > 0 {
+        system_program::transfer(
+            CpiContext::new(system_program.to_account_info(), system_program::Transfer {
+                from: player.to_account_info(),
+                to: profile.to_account_info(),
+            }),
+            config.spam_deposit
+        )?;
+        new_game.spam_deposit = config.spam_deposit;
+    }
+
+    if partial_fills {
+        new_game.set_partial_fills();
+    }
+
+    if mode == GameMode::Directional && !partial_fills {
+        open_games.insert(&games.games, market, games.games.len() as u32);
+    }
+
+    resize_account(games, payer, system_program, std::mem::size_of::<Game>())?;
+    games.games.push(new_game);
 
     Ok(())
 }
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{ constants, error::Error, state::{ Config, Season, WagerMint } };
+
+#[derive(Accounts)]
+#[instruction(season_id: u32)]
+pub struct CreateSeason<'info> {
+    #[account(mut, address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(
+        init,
+        seeds = [constants::SEASON_SEED, &season_id.to_le_bytes()],
+        bump,
+        payer = admin,
+        space = Season::len()
+    )]
+    pub season: Box<Account<'info, Season>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Opens a season over a mint, one at a time. Until it is closed, settlements of the mint take
+/// its accounts to rank the players and fund the prize pool with their fees. Fees kept since the
+/// last season, from games outside seasons, side bets or a season without a leaderboard, start
+/// the prize pool.
+pub fn create_season(
+    ctx: Context<CreateSeason>,
+    season_id: u32,
+    start_time: i64,
+    end_time: i64
+) -> Result<()> {
+    let CreateSeason { season, mint, wager_mint, .. } = ctx.accounts;
+
+    require!(start_time < end_time, Error::InvalidSeasonTimes);
+    require!(wager_mint.season.is_none(), Error::SeasonAlreadyRunning);
+    wager_mint.season = Some(season_id);
+
+    season.id = season_id;
+    season.mint = mint.key();
+    season.start_time = start_time;
+    season.end_time = end_time;
+    season.prize_pool = wager_mint.fees;
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, error::Error, state::Session };
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSession<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        seeds = [constants::SESSION_SEED, player.key().as_ref(), session_key.as_ref()],
+        bump,
+        payer = payer,
+        space = Session::len()
+    )]
+    pub session: Box<Account<'info, Session>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Authorizes `session_key` to play for the player until `expires_at`. Stakes placed with it
+/// come from the player's balance and add up to at most `spending_cap`.
+pub fn create_session(
+    ctx: Context<CreateSession>,
+    session_key: Pubkey,
+    expires_at: i64,
+    spending_cap: u64
+) -> Result<()> {
+    let CreateSession { player, session, .. } = ctx.accounts;
+
+    require!(expires_at > Clock::get()?.unix_timestamp, Error::InvalidSessionExpiry);
+
+    session.player = player.key();
+    session.session_key = session_key;
+    session.expires_at = expires_at;
+    session.spending_cap = spending_cap;
+
+    Ok(())
+}
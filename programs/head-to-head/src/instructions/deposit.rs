@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Balance, SolEscrow, WagerMint },
+    utils::Escrow,
+};
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::BALANCE_SEED, player.key().as_ref(), mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = Balance::len()
+    )]
+    pub balance: Box<Account<'info, Balance>>,
+
+    /// Token accounts are omitted for native SOL, which goes to the SOL escrow instead.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_account
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    let Deposit {
+        player,
+        mint,
+        wager_mint,
+        balance,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        token_program,
+        system_program,
+        ..
+    } = ctx.accounts;
+
+    require!(wager_mint.is_enabled, Error::MintDisabled);
+
+    let mut escrow = Escrow::new(
+        mint,
+        player_token_account.as_deref(),
+        vault_account.as_deref_mut(),
+        sol_escrow.as_deref(),
+        token_program.as_ref()
+    )?;
+    let received = escrow.deposit(player, amount, system_program)?;
+
+    balance.player = player.key();
+    balance.mint = mint.key();
+    balance.credit(received).ok_or(Error::ArithmeticOverflow)?;
+
+    wager_mint.lock(received).ok_or(Error::ArithmeticOverflow)?;
+    escrow.check_solvency(wager_mint)?;
+
+    Ok(())
+}
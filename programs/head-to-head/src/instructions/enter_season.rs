@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, error::Error, state::{ Season, SeasonScore } };
+
+#[derive(Accounts)]
+#[instruction(season_id: u32)]
+pub struct EnterSeason<'info> {
+    /// CHECK: Player the score is created for.
+    pub player: UncheckedAccount<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [constants::SEASON_SEED, &season_id.to_le_bytes()], bump)]
+    pub season: Box<Account<'info, Season>>,
+
+    #[account(
+        init,
+        seeds = [
+            constants::SEASON_SCORE_SEED,
+            &season_id.to_le_bytes(),
+            player.key().as_ref(),
+        ],
+        bump,
+        payer = payer,
+        space = SeasonScore::len()
+    )]
+    pub season_score: Box<Account<'info, SeasonScore>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates a player's score for a season. Settlements while the season is active need the scores
+/// of both players, so anyone can enter a player.
+pub fn enter_season(ctx: Context<EnterSeason>, season_id: u32) -> Result<()> {
+    let EnterSeason { player, season, season_score, .. } = ctx.accounts;
+
+    require!(!season.is_closed, Error::SeasonClosed);
+
+    season_score.season = season_id;
+    season_score.player = player.key();
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{
+        AllowlistEntry,
+        Balance,
+        Config,
+        Fill,
+        GameFills,
+        Games,
+        PlayerProfile,
+        Prices,
+        SolEscrow,
+        WagerMint,
+    },
+    utils::{ check_market_access, load_market_access, refund_spam_deposit, resize_account, Escrow },
+};
+
+/// Takes a portion of the opponent stake of a partial fill game.
+#[derive(Accounts)]
+#[instruction(game_index: u32)]
+pub struct FillGame<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    pub prices: Box<Account<'info, Prices>>,
+
+    pub opponent_prices: Option<Box<Account<'info, Prices>>>,
+
+    /// Token accounts are omitted for native SOL wagers, which go to the SOL escrow instead.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_account
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Pays the stake from the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    #[account(mut, seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::FILLS_SEED, &game_index.to_le_bytes()],
+        bump,
+        payer = payer,
+        space = GameFills::space(0)
+    )]
+    pub game_fills: Box<Account<'info, GameFills>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Host of the game and their profile, to refund the anti-spam deposit of the game.
+    #[account(mut)]
+    pub host: Option<SystemAccount<'info>>,
+
+    #[account(mut)]
+    pub host_profile: Option<Box<Account<'info, PlayerProfile>>>,
+
+    /// CHECK: `MarketAccess` of the game's market, uninitialized for markets open to everyone.
+    #[account(seeds = [constants::MARKET_ACCESS_SEED, &prices.market.to_le_bytes()], bump)]
+    pub market_access: UncheckedAccount<'info>,
+
+    /// Token account of the gate mint, for holders in gated markets.
+    pub access_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub allowlist_entry: Option<Box<Account<'info, AllowlistEntry>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn fill_game(ctx: Context<FillGame>, game_index: u32, amount: u64) -> Result<()> {
+    let FillGame {
+        player,
+        payer,
+        config,
+        mint,
+        wager_mint,
+        prices,
+        opponent_prices,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        games_account,
+        game_fills,
+        token_program,
+        system_program,
+        profile,
+        host,
+        host_profile,
+        market_access,
+        access_token_account,
+        allowlist_entry,
+    } = ctx.accounts;
+
+    require!((game_index as usize) < games_account.games.len(), Error::GameNotFound);
+
+    let game = &mut games_account.games[game_index as usize];
+
+    require!(game.mint == mint.key(), Error::InvalidMint);
+    require!(game.partial_fills, Error::PartialFillsNotAllowed);
+    require!(!game.is_closed, Error::GameAlreadyClosed);
+    require!(game.host != player.key(), Error::CannotJoinOwnGame);
+    require!(amount > 0 && amount <= game.unfilled_amount(), Error::InvalidFillAmount);
+    require!(game.is_joinable(Clock::get()?.unix_timestamp), Error::GameExpired);
+
+    profile.init(player.key());
+    require!(game.accepts_rating(profile.rating), Error::RatingOutOfBand);
+
+    let opponent_prices = opponent_prices.as_deref().map(|prices| &**prices);
+    require!(game.validate_feeds(prices, opponent_prices), Error::InvalidPricesAccount);
+
+    // Removing the gate of the market opens its running gated games to everyone
+    if game.is_gated {
+        match load_market_access(market_access)? {
+            Some(market_access) => {
+                check_market_access(
+                    &market_access,
+                    player.key(),
+                    access_token_account.as_deref(),
+                    allowlist_entry.as_deref()
+                )?;
+            }
+            None => {
+                game.is_gated = false;
+            }
+        }
+    }
+
+    require!(
+        game.check_join_threshold(prices, opponent_prices, config.threshold_decimals),
+        Error::PriceMovedTooMuch
+    );
+
+    let received = match balance {
+        Some(balance) => {
+            balance.debit(amount).ok_or(Error::InsufficientBalance)?;
+            amount
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            let received = escrow.deposit(player, amount, system_program)?;
+
+            wager_mint.lock(received).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+            received
+        }
+    };
+
+    game.fill(received).ok_or(Error::ArithmeticOverflow)?;
+    refund_spam_deposit(
+        game,
+        host.as_ref().map(|host| host.as_ref()),
+        host_profile.as_deref()
+    )?;
+
+    game_fills.game_index = game_index;
+    match game_fills.fills.iter_mut().find(|fill| fill.player == player.key()) {
+        Some(fill) => {
+            fill.amount = fill.amount.checked_add(received).ok_or(Error::ArithmeticOverflow)?;
+            profile.total_wagered = profile.total_wagered
+                .checked_add(received)
+                .ok_or(Error::ArithmeticOverflow)?;
+        }
+        None => {
+            let required_space = GameFills::space(game_fills.fills.len() + 1);
+            let current_space = game_fills.to_account_info().data_len();
+            if current_space < required_space {
+                resize_account(game_fills, payer, system_program, required_space - current_space)?;
+            }
+
+            game_fills.fills.push(Fill { player: player.key(), amount: received, is_claimed: false });
+            profile.record_wager(received).ok_or(Error::ArithmeticOverflow)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,19 +1,31 @@
 use anchor_lang::prelude::*;
 use crate::constants::{ self };
-use crate::state::Prices;
+use crate::error::Error;
+use crate::state::{ Config, Prices };
 
 #[derive(Clone, AnchorDeserialize, AnchorSerialize)]
 pub struct InitializePricesArgs {
     pub initial_price: u64,
     pub price_decimals: u8,
+    pub market: u16,
 }
 
 #[derive(Accounts)]
+#[instruction(args: InitializePricesArgs)]
 pub struct InitializePrices<'info> {
-    #[account(mut)]
+    #[account(mut, address = config.admin @ Error::AdminOnly)]
     pub signer: Signer<'info>,
 
-    #[account(init, seeds = [constants::PRICES_SEED], bump, payer = signer, space = 8 + 4 + 8 + 1)]
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        init,
+        seeds = [constants::PRICES_SEED, &args.market.to_le_bytes()],
+        bump,
+        payer = signer,
+        space = 8 + 4 + 8 + 1 + 4 + 8 + 2
+    )]
     pub prices: Box<Account<'info, Prices>>,
 
     pub system_program: Program<'info, System>,
@@ -21,10 +33,12 @@ pub struct InitializePrices<'info> {
 
 pub fn initialize_prices(ctx: Context<InitializePrices>, args: InitializePricesArgs) -> Result<()> {
     let InitializePrices { prices, .. } = ctx.accounts;
-    let InitializePricesArgs { initial_price, price_decimals, .. } = args;
+    let InitializePricesArgs { initial_price, price_decimals, market } = args;
 
     prices.prices.push(initial_price);
+    prices.timestamps.push(Clock::get()?.unix_timestamp);
     prices.decimals = price_decimals;
+    prices.market = market;
 
     Ok(())
 }
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::native_mint;
+
+use crate::{ constants, error::Error, state::{ Config, SolEscrow, WagerMint } };
+
+use super::WagerMintArgs;
+
+/// Registers native SOL for wagers under the native mint and creates the SOL escrow.
+#[derive(Accounts)]
+pub struct InitializeSolEscrow<'info> {
+    #[account(mut, address = config.admin @ Error::AdminOnly)]
+    pub signer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        init,
+        seeds = [constants::WAGER_MINT_SEED, native_mint::ID.as_ref()],
+        bump,
+        payer = signer,
+        space = WagerMint::len()
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(
+        init,
+        seeds = [constants::SOL_ESCROW_SEED],
+        bump,
+        payer = signer,
+        space = SolEscrow::len()
+    )]
+    pub sol_escrow: Box<Account<'info, SolEscrow>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_sol_escrow(ctx: Context<InitializeSolEscrow>, args: WagerMintArgs) -> Result<()> {
+    let wager_mint = &mut ctx.accounts.wager_mint;
+
+    require!(args.is_valid(), Error::InvalidBetLimits);
+
+    wager_mint.mint = native_mint::ID;
+    wager_mint.min_bet_size = args.min_bet_size;
+    wager_mint.max_bet_size = args.max_bet_size;
+    wager_mint.is_enabled = true;
+
+    Ok(())
+}
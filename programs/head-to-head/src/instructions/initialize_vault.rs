@@ -1,32 +1,68 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{ Mint, Token, TokenAccount };
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
 
-use crate::{ constants, state::Config };
+use crate::{ constants, error::Error, state::{ Config, WagerMint } };
 
+#[derive(Clone, AnchorDeserialize, AnchorSerialize)]
+pub struct WagerMintArgs {
+    pub min_bet_size: u64,
+    pub max_bet_size: u64,
+}
+
+impl WagerMintArgs {
+    pub fn is_valid(&self) -> bool {
+        self.min_bet_size > 0 && self.min_bet_size <= self.max_bet_size
+    }
+}
+
+/// Registers `mint` for wagers and creates its vault.
 #[derive(Accounts)]
 pub struct InitializeVault<'info> {
-    #[account(mut)]
+    #[account(mut, address = config.admin @ Error::AdminOnly)]
     pub signer: Signer<'info>,
 
-    #[account(mut, seeds = [constants::CONFIG_SEED], bump)]
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
     pub config: Box<Account<'info, Config>>,
 
     #[account(
         init,
-        seeds = [constants::VAULT_SEED],
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        payer = signer,
+        space = WagerMint::len()
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(
+        init,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
         bump,
         payer = signer,
         token::mint = mint,
-        token::authority = vault
+        token::authority = vault,
+        token::token_program = token_program
     )]
-    pub vault: Box<Account<'info, TokenAccount>>,
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    // #[account(constraint = config.mint == mint.key())]
-    pub mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mint::token_program = token_program,
+        constraint = mint.key() != native_mint::ID @ Error::NativeMintVault
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn initialize_vault(_ctx: Context<InitializeVault>) -> Result<()> {
+pub fn initialize_vault(ctx: Context<InitializeVault>, args: WagerMintArgs) -> Result<()> {
+    let InitializeVault { wager_mint, mint, .. } = ctx.accounts;
+
+    require!(args.is_valid(), Error::InvalidBetLimits);
+
+    wager_mint.mint = mint.key();
+    wager_mint.min_bet_size = args.min_bet_size;
+    wager_mint.max_bet_size = args.max_bet_size;
+    wager_mint.is_enabled = true;
+
     Ok(())
 }
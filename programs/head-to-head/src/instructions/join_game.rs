@@ -1,50 +1,142 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{ Token, TokenAccount };
-
-use crate::{ constants, error::Error, state::{ Config, Games, Prices }, utils::transfer_tokens };
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{
+        AllowlistEntry,
+        Balance,
+        Config,
+        Games,
+        PlayerProfile,
+        Prices,
+        Session,
+        SolEscrow,
+        WagerMint,
+    },
+    utils::{
+        authorize_session,
+        check_market_access,
+        load_market_access,
+        refund_spam_deposit,
+        Escrow,
+    },
+};
 
 #[derive(Accounts)]
 pub struct JoinGame<'info> {
+    /// Player's wallet, or a session key of the player passed with its `session`.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Player the instruction acts for, checked against `authority` and `session`.
+    #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
     #[account(mut)]
-    pub player: Signer<'info>,
+    pub session: Option<Box<Account<'info, Session>>>,
 
     #[account(seeds = [constants::CONFIG_SEED], bump)]
     pub config: Box<Account<'info, Config>>,
 
-    #[account(seeds = [constants::PRICES_SEED], bump)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
     pub prices: Box<Account<'info, Prices>>,
 
+    pub opponent_prices: Option<Box<Account<'info, Prices>>>,
+
+    /// Token accounts are omitted for native SOL wagers, which go to the SOL escrow instead.
     #[account(
         mut,
-        constraint = player_token_account.mint == config.mint,
+        constraint = player_token_account.mint == mint.key(),
         constraint = player_token_account.owner == player.key()
     )]
-    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
         mut,
-        seeds = [constants::VAULT_SEED],
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
         bump,
-        token::mint = config.mint,
+        token::mint = mint,
         token::authority = vault_account
     )]
-    pub vault_account: Box<Account<'info, TokenAccount>>,
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Pays the stake from the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
 
     #[account(mut, seeds = [constants::GAMES_SEED], bump)]
     pub games_account: Box<Account<'info, Games>>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Host of the game and their profile, to refund the anti-spam deposit of the game.
+    #[account(mut)]
+    pub host: Option<SystemAccount<'info>>,
+
+    #[account(mut)]
+    pub host_profile: Option<Box<Account<'info, PlayerProfile>>>,
+
+    /// CHECK: `MarketAccess` of the game's market, uninitialized for markets open to everyone.
+    #[account(seeds = [constants::MARKET_ACCESS_SEED, &prices.market.to_le_bytes()], bump)]
+    pub market_access: UncheckedAccount<'info>,
+
+    /// Token account of the gate mint, for holders in gated markets.
+    pub access_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub allowlist_entry: Option<Box<Account<'info, AllowlistEntry>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
 }
 
 pub fn join_game(ctx: Context<JoinGame>, game_index: u32) -> Result<()> {
     let JoinGame {
+        authority,
+        session,
         player,
         games_account,
         player_token_account,
         vault_account,
+        sol_escrow,
+        balance,
         config,
         token_program,
         prices,
+        opponent_prices,
+        mint,
+        wager_mint,
+        system_program,
+        profile,
+        host,
+        host_profile,
+        market_access,
+        access_token_account,
+        allowlist_entry,
         ..
     } = ctx.accounts;
 
@@ -52,32 +144,81 @@ pub fn join_game(ctx: Context<JoinGame>, game_index: u32) -> Result<()> {
 
     let game = &mut games_account.games[game_index as usize];
 
+    require!(game.mint == mint.key(), Error::InvalidMint);
     require!(!game.is_closed, Error::GameAlreadyClosed);
     require!(game.host != player.key(), Error::CannotJoinOwnGame);
+    require!(!game.partial_fills, Error::PartialFillsOnly);
     require!(game.opponent.is_none(), Error::GameAlreadyJoined);
+    let now = Clock::get()?.unix_timestamp;
+    require!(game.is_joinable(now), Error::GameExpired);
+    require!(game.accepts_opponent(player.key(), now), Error::GameReserved);
+
+    profile.init(player.key());
+    require!(game.accepts_rating(profile.rating), Error::RatingOutOfBand);
+
+    let opponent_prices = opponent_prices.as_deref().map(|prices| &**prices);
+    require!(game.validate_feeds(prices, opponent_prices), Error::InvalidPricesAccount);
+
+    // Removing the gate of the market opens its running gated games to everyone
+    if game.is_gated {
+        match load_market_access(market_access)? {
+            Some(market_access) => {
+                check_market_access(
+                    &market_access,
+                    player.key(),
+                    access_token_account.as_deref(),
+                    allowlist_entry.as_deref()
+                )?;
+            }
+            None => {
+                game.is_gated = false;
+            }
+        }
+    }
 
     require!(
-        game
-            .check_price_fluctuation(
-                &prices.prices,
-                config.join_threshold_percent,
-                prices.decimals,
-                config.threshold_decimals
-            )
-            .is_none(),
+        game.check_join_threshold(prices, opponent_prices, config.threshold_decimals),
         Error::PriceMovedTooMuch
     );
 
-    transfer_tokens(
-        player_token_account.to_account_info(),
-        vault_account.to_account_info(),
-        player.to_account_info(),
-        config.bet_size,
-        token_program.to_account_info(),
-        None
+    let is_session = authorize_session(
+        player,
+        authority,
+        session.as_deref_mut().map(|session| &mut **session),
+        game.opponent_amount,
+        now
+    )?;
+    require!(!is_session || balance.is_some(), Error::SessionRequiresBalance);
+
+    let received = match balance {
+        Some(balance) => {
+            balance.debit(game.opponent_amount).ok_or(Error::InsufficientBalance)?;
+            game.opponent_amount
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            let received = escrow.deposit(authority, game.opponent_amount, system_program)?;
+
+            wager_mint.lock(received).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+            received
+        }
+    };
+
+    game.join(player.key(), received);
+    refund_spam_deposit(
+        game,
+        host.as_ref().map(|host| host.as_ref()),
+        host_profile.as_deref()
     )?;
 
-    game.join(player.key());
+    profile.record_wager(received).ok_or(Error::ArithmeticOverflow)?;
 
     Ok(())
 }
@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Config, Game, Games, Intent, IntentQueue, Prices },
+    utils::resize_account,
+};
+
+/// Pairs queued intents into games, oldest first. Games start from the latest price, and intents
+/// only match while the price stays within the join threshold of their submission, like a join.
+/// Anyone can crank it and pays for the games account growth. The wallets and profiles of the
+/// matched players are passed as remaining accounts to refund their anti-spam deposits.
+#[derive(Accounts)]
+#[instruction(market: u16)]
+pub struct MatchIntents<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(seeds = [constants::PRICES_SEED, &market.to_le_bytes()], bump)]
+    pub prices_account: Box<Account<'info, Prices>>,
+
+    #[account(mut, seeds = [constants::INTENTS_SEED, &market.to_le_bytes()], bump)]
+    pub intent_queue: Box<Account<'info, IntentQueue>>,
+
+    #[account(mut, seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn match_intents(ctx: Context<MatchIntents>, market: u16, max_matches: u8) -> Result<()> {
+    let MatchIntents {
+        signer,
+        config,
+        prices_account: prices,
+        intent_queue,
+        games_account: games,
+        system_program,
+    } = ctx.accounts;
+
+    // Intents the price moved away from since their submission wait for their players to cancel
+    let mut is_live: Vec<bool> = intent_queue.intents
+        .iter()
+        .map(|intent| {
+            intent
+                .game(market, intent.price_index)
+                .check_join_threshold(prices, None, config.threshold_decimals)
+        })
+        .collect();
+
+    let mut matched: u8 = 0;
+
+    while matched < max_matches {
+        let Some((host_index, opponent_index)) = intent_queue.next_match(&is_live) else {
+            break;
+        };
+
+        // The opponent is always queued after the host
+        let opponent = intent_queue.intents.remove(opponent_index);
+        let host = intent_queue.intents.remove(host_index);
+        is_live.remove(opponent_index);
+        is_live.remove(host_index);
+
+        refund_deposit(&host, ctx.remaining_accounts, ctx.program_id)?;
+        refund_deposit(&opponent, ctx.remaining_accounts, ctx.program_id)?;
+
+        let mut new_game = host.game(market, prices.last_index());
+        new_game.set_stakes(host.amount, 0);
+        new_game.join(opponent.player, opponent.amount);
+
+        resize_account(games, signer, system_program, std::mem::size_of::<Game>())?;
+        games.games.push(new_game);
+
+        msg!(
+            "Matched intents {} and {} into game {}",
+            host.id,
+            opponent.id,
+            games.games.len() - 1
+        );
+        matched += 1;
+    }
+
+    require!(matched > 0, Error::NoMatchingIntents);
+
+    Ok(())
+}
+
+/// Returns the anti-spam deposit of a matched intent from the player's profile to their wallet,
+/// both found among `accounts`.
+fn refund_deposit(intent: &Intent, accounts: &[AccountInfo], program_id: &Pubkey) -> Result<()> {
+    if intent.spam_deposit == 0 {
+        return Ok(());
+    }
+
+    let (profile_key, _) = Pubkey::find_program_address(
+        &[constants::PROFILE_SEED, intent.player.as_ref()],
+        program_id
+    );
+    let player = accounts.iter().find(|account| account.key() == intent.player);
+    let profile = accounts.iter().find(|account| account.key() == profile_key);
+
+    match (player, profile) {
+        (Some(player), Some(profile)) => {
+            profile.sub_lamports(intent.spam_deposit)?;
+            player.add_lamports(intent.spam_deposit)?;
+            Ok(())
+        }
+        _ => err!(Error::MissingIntentAccounts),
+    }
+}
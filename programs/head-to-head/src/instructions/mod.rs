@@ -10,17 +10,119 @@ pub use initialize_prices::*;
 mod initialize_vault;
 pub use initialize_vault::*;
 
+mod initialize_sol_escrow;
+pub use initialize_sol_escrow::*;
+
+mod update_wager_mint;
+pub use update_wager_mint::*;
+
+mod reconcile_vault;
+pub use reconcile_vault::*;
+
+mod create_session;
+pub use create_session::*;
+
+mod revoke_session;
+pub use revoke_session::*;
+
+mod deposit;
+pub use deposit::*;
+
+mod withdraw;
+pub use withdraw::*;
+
 mod create_game;
 pub use create_game::*;
 
 mod join_game;
 pub use join_game::*;
 
+mod quick_join;
+pub use quick_join::*;
+
+mod fill_game;
+pub use fill_game::*;
+
 mod withdraw_from_game;
 pub use withdraw_from_game::*;
 
+mod cancel_game;
+pub use cancel_game::*;
+
+mod withdraw_cancel;
+pub use withdraw_cancel::*;
+
+mod rematch;
+pub use rematch::*;
+
+mod place_side_bet;
+pub use place_side_bet::*;
+
+mod claim_side_bet;
+pub use claim_side_bet::*;
+
 mod claim_winnings;
 pub use claim_winnings::*;
 
+mod claim_fill;
+pub use claim_fill::*;
+
+mod settle_fill;
+pub use settle_fill::*;
+
+mod claim_many;
+pub use claim_many::*;
+
+mod submit_intent;
+pub use submit_intent::*;
+
+mod cancel_intent;
+pub use cancel_intent::*;
+
+mod match_intents;
+pub use match_intents::*;
+
+mod register_referrer;
+pub use register_referrer::*;
+
+mod open_referral_rewards;
+pub use open_referral_rewards::*;
+
+mod claim_referral_rewards;
+pub use claim_referral_rewards::*;
+
+mod create_season;
+pub use create_season::*;
+
+mod enter_season;
+pub use enter_season::*;
+
+mod close_season;
+pub use close_season::*;
+
+mod claim_season_prize;
+pub use claim_season_prize::*;
+
 mod add_price;
 pub use add_price::*;
+
+mod set_threshold_tiers;
+pub use set_threshold_tiers::*;
+
+mod set_protocol_fee;
+pub use set_protocol_fee::*;
+
+mod set_spam_limits;
+pub use set_spam_limits::*;
+
+mod set_market_access;
+pub use set_market_access::*;
+
+mod remove_market_access;
+pub use remove_market_access::*;
+
+mod add_to_allowlist;
+pub use add_to_allowlist::*;
+
+mod remove_from_allowlist;
+pub use remove_from_allowlist::*;
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{ constants, state::{ ReferralRewards, WagerMint } };
+
+#[derive(Accounts)]
+#[instruction(referrer: Pubkey)]
+pub struct OpenReferralRewards<'info> {
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()], bump, has_one = mint)]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(
+        init,
+        seeds = [constants::REFERRAL_SEED, referrer.as_ref(), mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = ReferralRewards::len()
+    )]
+    pub referral_rewards: Box<Account<'info, ReferralRewards>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Opens the rewards account of `referrer` for another mint. Settlements of referred players
+/// require it, so anyone can open it.
+pub fn open_referral_rewards(ctx: Context<OpenReferralRewards>, referrer: Pubkey) -> Result<()> {
+    let OpenReferralRewards { mint, referral_rewards, .. } = ctx.accounts;
+
+    referral_rewards.referrer = referrer;
+    referral_rewards.mint = mint.key();
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Balance, Config, Games, Prices, SideBet, SidePool, SolEscrow, WagerMint },
+    utils::Escrow,
+};
+
+#[derive(Accounts)]
+#[instruction(game_index: u32)]
+pub struct PlaceSideBet<'info> {
+    #[account(mut)]
+    pub backer: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    pub prices: Box<Account<'info, Prices>>,
+
+    /// Price feed of the opponent market, required for relative games.
+    pub opponent_prices: Option<Box<Account<'info, Prices>>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::SIDE_POOL_SEED, &game_index.to_le_bytes()],
+        bump,
+        payer = payer,
+        space = SidePool::len()
+    )]
+    pub side_pool: Box<Account<'info, SidePool>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::SIDE_BET_SEED, &game_index.to_le_bytes(), backer.key().as_ref()],
+        bump,
+        payer = payer,
+        space = SideBet::len()
+    )]
+    pub side_bet: Box<Account<'info, SideBet>>,
+
+    /// Token accounts are omitted for native SOL wagers, which go to the SOL escrow.
+    #[account(
+        mut,
+        constraint = backer_token_account.mint == mint.key(),
+        constraint = backer_token_account.owner == backer.key()
+    )]
+    pub backer_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Pays the stake from the backer's balance instead of their wallet.
+    #[account(mut, has_one = mint, constraint = balance.player == backer.key())]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Backs the host or the opponent of a joined game with `amount`, as long as the prices have not
+/// decided the game yet.
+pub fn place_side_bet(
+    ctx: Context<PlaceSideBet>,
+    game_index: u32,
+    backs_host: bool,
+    amount: u64
+) -> Result<()> {
+    let PlaceSideBet {
+        backer,
+        config,
+        mint,
+        wager_mint,
+        games_account,
+        prices,
+        opponent_prices,
+        side_pool,
+        side_bet,
+        backer_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        token_program,
+        system_program,
+        ..
+    } = ctx.accounts;
+
+    require!((game_index as usize) < games_account.games.len(), Error::GameNotFound);
+
+    let game = &games_account.games[game_index as usize];
+    require!(game.mint == mint.key(), Error::InvalidMint);
+    require!(wager_mint.is_enabled, Error::MintDisabled);
+    require!(wager_mint.accepts(amount), Error::InvalidBetSize);
+    require!(game.counterparty(backer.key()).is_none(), Error::PlayerCannotBackSide);
+
+    let opponent_prices = opponent_prices.as_deref().map(|prices| &**prices);
+    require!(game.validate_feeds(prices, opponent_prices), Error::InvalidPricesAccount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let is_undecided =
+        side_pool.host_won.is_none() &&
+        !game.is_expired(now) &&
+        game.check_result(prices, opponent_prices, config.threshold_decimals, now).is_none();
+    require!(game.opponent.is_some() && !game.is_closed && is_undecided, Error::SideBetsClosed);
+
+    if side_bet.amount == 0 {
+        side_bet.game_index = game_index;
+        side_bet.backer = backer.key();
+        side_bet.backs_host = backs_host;
+    }
+    require!(side_bet.backs_host == backs_host, Error::SideMismatch);
+
+    let received = match balance {
+        Some(balance) => {
+            balance.debit(amount).ok_or(Error::InsufficientBalance)?;
+            amount
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                backer_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            let received = escrow.deposit(backer, amount, system_program)?;
+
+            wager_mint.lock(received).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+            received
+        }
+    };
+
+    side_pool.game_index = game_index;
+    side_pool.mint = mint.key();
+    side_pool.back(backs_host, received).ok_or(Error::ArithmeticOverflow)?;
+    side_bet.amount = side_bet.amount.checked_add(received).ok_or(Error::ArithmeticOverflow)?;
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Balance, Config, Games, OpenGames, PlayerProfile, Prices, SolEscrow, WagerMint },
+    utils::{ refund_spam_deposit, Escrow },
+};
+
+#[derive(Clone, AnchorDeserialize, AnchorSerialize)]
+pub struct QuickJoinArgs {
+    pub market: u16,
+    /// Side the player takes (true = UP), the host of the joined game predicted the opposite.
+    pub prediction: bool,
+    pub max_stake: u64,
+}
+
+/// Joins the oldest open directional game of the market that fits the player's side and stake.
+/// Games of gated markets are skipped. When the chosen game holds an anti-spam deposit, its host
+/// and host profile must be passed.
+#[derive(Accounts)]
+#[instruction(args: QuickJoinArgs)]
+pub struct QuickJoin<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(seeds = [constants::PRICES_SEED, &args.market.to_le_bytes()], bump)]
+    pub prices: Box<Account<'info, Prices>>,
+
+    #[account(mut, seeds = [constants::OPEN_GAMES_SEED, &args.market.to_le_bytes()], bump)]
+    pub open_games: Box<Account<'info, OpenGames>>,
+
+    /// Token accounts are omitted for native SOL wagers, which go to the SOL escrow instead.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_account
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Pays the stake from the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    #[account(mut, seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Host of the game and their profile, to refund the anti-spam deposit of the game.
+    #[account(mut)]
+    pub host: Option<SystemAccount<'info>>,
+
+    #[account(mut)]
+    pub host_profile: Option<Box<Account<'info, PlayerProfile>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn quick_join(ctx: Context<QuickJoin>, args: QuickJoinArgs) -> Result<()> {
+    let QuickJoin {
+        player,
+        config,
+        mint,
+        wager_mint,
+        prices,
+        open_games,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        games_account,
+        token_program,
+        system_program,
+        profile,
+        host,
+        host_profile,
+        ..
+    } = ctx.accounts;
+
+    profile.init(player.key());
+    open_games.prune(&games_account.games);
+
+    let now = Clock::get()?.unix_timestamp;
+    let position = open_games.game_indices
+        .iter()
+        .position(|&index| {
+            let game = &games_account.games[index as usize];
+            game.mint == mint.key() &&
+                !game.partial_fills &&
+                !game.is_gated &&
+                game.host != player.key() &&
+                game.accepts_opponent(player.key(), now) &&
+                game.host_prediction != args.prediction &&
+                game.opponent_amount <= args.max_stake &&
+                game.accepts_rating(profile.rating) &&
+                game.check_join_threshold(prices, None, config.threshold_decimals)
+        })
+        .ok_or(Error::NoCompatibleGame)?;
+    let game_index = open_games.game_indices.remove(position);
+
+    let game = &mut games_account.games[game_index as usize];
+
+    let received = match balance {
+        Some(balance) => {
+            balance.debit(game.opponent_amount).ok_or(Error::InsufficientBalance)?;
+            game.opponent_amount
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            let received = escrow.deposit(player, game.opponent_amount, system_program)?;
+
+            wager_mint.lock(received).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+            received
+        }
+    };
+
+    game.join(player.key(), received);
+    refund_spam_deposit(
+        game,
+        host.as_ref().map(|host| host.as_ref()),
+        host_profile.as_deref()
+    )?;
+
+    profile.record_wager(received).ok_or(Error::ArithmeticOverflow)?;
+    msg!("Joined game {}", game_index);
+
+    Ok(())
+}
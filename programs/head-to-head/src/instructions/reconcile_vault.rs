@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_interface::{ Mint, TokenAccount };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ SolEscrow, VaultReport, WagerMint },
+    utils::sol_escrow_balance,
+};
+
+/// Compares the vault of `mint` with the stakes locked in its games, anyone can call it.
+#[derive(Accounts)]
+pub struct ReconcileVault<'info> {
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()], bump, has_one = mint)]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    /// Omitted for native SOL, which is held by the SOL escrow.
+    #[account(seeds = [constants::VAULT_SEED, mint.key().as_ref()], bump, token::mint = mint)]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+}
+
+pub fn reconcile_vault(ctx: Context<ReconcileVault>) -> Result<VaultReport> {
+    let ReconcileVault { mint, wager_mint, vault_account, sol_escrow } = ctx.accounts;
+
+    let balance = if mint.key() == native_mint::ID {
+        sol_escrow_balance(sol_escrow.as_ref().ok_or(Error::MissingEscrowAccounts)?)?
+    } else {
+        vault_account.as_ref().ok_or(Error::MissingEscrowAccounts)?.amount
+    };
+
+    let report = VaultReport::new(wager_mint, balance);
+    msg!(
+        "Vault balance: {}, locked: {}, surplus: {}, deficit: {}",
+        report.balance,
+        report.locked_amount,
+        report.surplus,
+        report.deficit
+    );
+
+    Ok(report)
+}
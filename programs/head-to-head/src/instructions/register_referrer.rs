@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{ constants, error::Error, state::{ PlayerProfile, ReferralRewards } };
+
+#[derive(Accounts)]
+#[instruction(referrer: Pubkey)]
+pub struct RegisterReferrer<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Mint the player wagers in, the referrer's rewards are credited in it.
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::REFERRAL_SEED, referrer.as_ref(), mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = ReferralRewards::len()
+    )]
+    pub referral_rewards: Box<Account<'info, ReferralRewards>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Stores the player's referrer once and opens the referrer's rewards account for `mint`.
+pub fn register_referrer(ctx: Context<RegisterReferrer>, referrer: Pubkey) -> Result<()> {
+    let RegisterReferrer { player, profile, mint, referral_rewards, .. } = ctx.accounts;
+
+    require!(profile.referrer.is_none(), Error::ReferrerAlreadySet);
+    require!(referrer != player.key(), Error::InvalidReferrer);
+
+    profile.init(player.key());
+    profile.referrer = Some(referrer);
+
+    referral_rewards.referrer = referrer;
+    referral_rewards.mint = mint.key();
+
+    Ok(())
+}
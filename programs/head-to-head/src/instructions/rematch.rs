@@ -0,0 +1,220 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{
+        AllowlistEntry,
+        Balance,
+        Config,
+        Game,
+        GameMode,
+        Games,
+        OpenGames,
+        PlayerProfile,
+        Prices,
+        SolEscrow,
+        WagerMint,
+    },
+    utils::{ check_market_access, load_market_access, resize_account, Escrow },
+};
+
+#[derive(Accounts)]
+pub struct Rematch<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    /// Price feed of the market of the previous game, the rematch starts from its latest price.
+    pub prices_account: Box<Account<'info, Prices>>,
+
+    /// Price feed of the opponent market of a relative game.
+    pub opponent_prices_account: Option<Box<Account<'info, Prices>>>,
+
+    #[account(mut, seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::OPEN_GAMES_SEED, &prices_account.market.to_le_bytes()],
+        bump,
+        payer = payer,
+        space = OpenGames::len()
+    )]
+    pub open_games: Box<Account<'info, OpenGames>>,
+
+    /// Token accounts are omitted for native SOL wagers, which go to the SOL escrow.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Pays the stake from the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    #[account(mut, seeds = [constants::PROFILE_SEED, player.key().as_ref()], bump)]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// CHECK: `MarketAccess` of the market, uninitialized for markets open to everyone.
+    #[account(seeds = [constants::MARKET_ACCESS_SEED, &prices_account.market.to_le_bytes()], bump)]
+    pub market_access: UncheckedAccount<'info>,
+
+    /// Token account of the gate mint, for holders in gated markets.
+    pub access_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub allowlist_entry: Option<Box<Account<'info, AllowlistEntry>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Offers the other player of a settled game a new one on the same terms from the latest price,
+/// reserved for them for `REMATCH_WINDOW` seconds before it opens to everyone. Each game is
+/// rematched once, and the mint and market gate are checked again as when creating a game.
+pub fn rematch(ctx: Context<Rematch>, game_index: u32, swap_predictions: bool) -> Result<()> {
+    let Rematch {
+        player,
+        payer,
+        config,
+        mint,
+        wager_mint,
+        prices_account: prices,
+        opponent_prices_account: opponent_prices,
+        games_account: games,
+        open_games,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        profile,
+        market_access,
+        access_token_account,
+        allowlist_entry,
+        token_program,
+        system_program,
+    } = ctx.accounts;
+
+    require!((game_index as usize) < games.games.len(), Error::GameNotFound);
+
+    let game = &games.games[game_index as usize];
+    require!(game.mint == mint.key(), Error::InvalidMint);
+    require!(!game.partial_fills && game.result.is_some(), Error::RematchNotAllowed);
+    require!(prices.market == game.market, Error::InvalidPricesAccount);
+    require!(wager_mint.is_enabled, Error::MintDisabled);
+    // Stakes were priced from a bet size of half the pot
+    let bet_size = game.pot().ok_or(Error::ArithmeticOverflow)? / 2;
+    require!(wager_mint.accepts(bet_size), Error::InvalidBetSize);
+
+    let is_gated = match load_market_access(market_access)? {
+        Some(market_access) => {
+            check_market_access(
+                &market_access,
+                player.key(),
+                access_token_account.as_deref(),
+                allowlist_entry.as_deref()
+            )?;
+            true
+        }
+        None => false,
+    };
+    require!(
+        config.max_open_games_per_player == 0 ||
+            games.open_games_of(player.key()) < (config.max_open_games_per_player as usize),
+        Error::PlayerOpenGamesLimit
+    );
+
+    let opponent_price_index = match (game.opponent_market, opponent_prices) {
+        (Some(market), Some(opponent_prices)) if opponent_prices.market == market => {
+            Some(opponent_prices.last_index())
+        }
+        (None, None) => None,
+        _ => {
+            return err!(Error::InvalidOpponentMarket);
+        }
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut new_game = game
+        .rematch(player.key(), swap_predictions, prices.last_index(), opponent_price_index, now)
+        .ok_or(Error::RematchNotAllowed)?;
+    new_game.is_gated = is_gated;
+    games.games[game_index as usize].is_rematched = true;
+
+    let received = match balance {
+        Some(balance) => {
+            balance.debit(new_game.amount).ok_or(Error::InsufficientBalance)?;
+            new_game.amount
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            let received = escrow.deposit(player, new_game.amount, system_program)?;
+
+            wager_mint.lock(received).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+            received
+        }
+    };
+    new_game.set_stakes(received, new_game.opponent_amount);
+
+    profile.record_wager(received).ok_or(Error::ArithmeticOverflow)?;
+    new_game.set_rating_band(profile.rating, 0);
+
+    // The anti-spam deposit waits in the host's profile until the game is joined or withdrawn
+    if config.spam_deposit > 0 {
+        system_program::transfer(
+            CpiContext::new(system_program.to_account_info(), system_program::Transfer {
+                from: player.to_account_info(),
+                to: profile.to_account_info(),
+            }),
+            config.spam_deposit
+        )?;
+        new_game.spam_deposit = config.spam_deposit;
+    }
+
+    if new_game.mode == GameMode::Directional {
+        open_games.insert(&games.games, new_game.market, games.games.len() as u32);
+    }
+
+    resize_account(games, payer, system_program, std::mem::size_of::<Game>())?;
+    games.games.push(new_game);
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, error::Error, state::{ AllowlistEntry, Config } };
+
+#[derive(Accounts)]
+#[instruction(market: u16, player: Pubkey)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(mut, address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [constants::ALLOWLIST_SEED, &market.to_le_bytes(), player.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Box<Account<'info, AllowlistEntry>>,
+}
+
+pub fn remove_from_allowlist(
+    _ctx: Context<RemoveFromAllowlist>,
+    _market: u16,
+    _player: Pubkey
+) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, error::Error, state::{ Config, MarketAccess } };
+
+#[derive(Accounts)]
+#[instruction(market: u16)]
+pub struct RemoveMarketAccess<'info> {
+    #[account(mut, address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [constants::MARKET_ACCESS_SEED, &market.to_le_bytes()],
+        bump
+    )]
+    pub market_access: Box<Account<'info, MarketAccess>>,
+}
+
+/// Opens a gated market to everyone. Its open gated games are too: joins and fills find the gate
+/// gone and clear their `is_gated` flag.
+pub fn remove_market_access(_ctx: Context<RemoveMarketAccess>, _market: u16) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, state::Session };
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct RevokeSession<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        close = player,
+        seeds = [constants::SESSION_SEED, player.key().as_ref(), session_key.as_ref()],
+        bump
+    )]
+    pub session: Box<Account<'info, Session>>,
+}
+
+pub fn revoke_session(_ctx: Context<RevokeSession>, _session_key: Pubkey) -> Result<()> {
+    Ok(())
+}
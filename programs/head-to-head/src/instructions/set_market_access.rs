@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, error::Error, state::{ Config, MarketAccess } };
+
+#[derive(Accounts)]
+#[instruction(market: u16)]
+pub struct SetMarketAccess<'info> {
+    #[account(mut, address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::MARKET_ACCESS_SEED, &market.to_le_bytes()],
+        bump,
+        payer = admin,
+        space = MarketAccess::len()
+    )]
+    pub market_access: Box<Account<'info, MarketAccess>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Gates a market, or changes its gate mint. Games created before keep their access rules.
+pub fn set_market_access(
+    ctx: Context<SetMarketAccess>,
+    market: u16,
+    gate_mint: Option<Pubkey>
+) -> Result<()> {
+    let market_access = &mut ctx.accounts.market_access;
+    market_access.market = market;
+    market_access.gate_mint = gate_mint;
+
+    Ok(())
+}
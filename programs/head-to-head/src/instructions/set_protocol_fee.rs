@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use crate::{
+    constants::{ CONFIG_SEED, MAX_FEE_BPS, MAX_REFERRAL_SHARE_BPS },
+    error::Error,
+    state::*,
+};
+
+#[derive(Accounts)]
+pub struct SetProtocolFee<'info> {
+    #[account(address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+}
+
+/// Sets the fee taken from every payout, accrued per mint to fund season prizes, and the share
+/// of it credited to referrers.
+pub fn set_protocol_fee(
+    ctx: Context<SetProtocolFee>,
+    fee_bps: u16,
+    referral_share_bps: u16
+) -> Result<()> {
+    require!(
+        fee_bps <= MAX_FEE_BPS && referral_share_bps <= MAX_REFERRAL_SHARE_BPS,
+        Error::InvalidFee
+    );
+
+    let config = &mut ctx.accounts.config;
+    config.fee_bps = fee_bps;
+    config.referral_share_bps = referral_share_bps;
+
+    Ok(())
+}
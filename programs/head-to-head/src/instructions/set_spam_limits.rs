@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+use crate::{ constants::CONFIG_SEED, error::Error, state::* };
+
+#[derive(Accounts)]
+pub struct SetSpamLimits<'info> {
+    #[account(address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+}
+
+/// Limits the open challenges of each player and sets the anti-spam deposit of new ones.
+/// Running games keep the deposit they were created with.
+pub fn set_spam_limits(
+    ctx: Context<SetSpamLimits>,
+    max_open_games_per_player: u16,
+    spam_deposit: u64
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.max_open_games_per_player = max_open_games_per_player;
+    config.spam_deposit = spam_deposit;
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use crate::{ constants::CONFIG_SEED, error::Error, state::* };
+
+#[derive(Accounts)]
+pub struct SetThresholdTiers<'info> {
+    #[account(address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+}
+
+/// Replaces the tier list. Running games keep the thresholds they were created with,
+/// new games refer to tiers by their position in `tiers`.
+pub fn set_threshold_tiers(
+    ctx: Context<SetThresholdTiers>,
+    tiers: Vec<ThresholdTier>
+) -> Result<()> {
+    require!(ThresholdTier::is_valid_list(&tiers), Error::InvalidThresholdTiers);
+
+    ctx.accounts.config.threshold_tiers = tiers;
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ GameFills, Games, PlayerProfile, Season, SeasonScore, WagerMint },
+    utils::elo_ratings,
+};
+
+#[derive(Accounts)]
+#[instruction(game_index: u32, filler: Pubkey)]
+pub struct SettleFill<'info> {
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [constants::WAGER_MINT_SEED, wager_mint.mint.as_ref()], bump)]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+
+    #[account(mut, seeds = [constants::FILLS_SEED, &game_index.to_le_bytes()], bump)]
+    pub game_fills: Box<Account<'info, GameFills>>,
+
+    /// Profile of the filler, to record the loss.
+    #[account(mut, seeds = [constants::PROFILE_SEED, filler.as_ref()], bump)]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Profile of the host, who won the game, rated against the filler.
+    #[account(mut, seeds = [constants::PROFILE_SEED, host_profile.player.as_ref()], bump)]
+    pub host_profile: Box<Account<'info, PlayerProfile>>,
+
+    /// Season of the mint that is not closed yet, required until it is. While it is active the
+    /// season score of the filler is required too, and created if missing.
+    #[account(mut)]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [
+            constants::SEASON_SCORE_SEED,
+            &wager_mint.season.unwrap_or_default().to_le_bytes(),
+            filler.as_ref(),
+        ],
+        bump,
+        payer = payer,
+        space = SeasonScore::len()
+    )]
+    pub season_score: Option<Box<Account<'info, SeasonScore>>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Records the loss of a filler of a partial fill game the host won, once the host's claim
+/// settled it: the filler's profile and season score take the loss and the filler is rated
+/// against the host, as `claim_fill` does for winning fillers. Anyone can settle a fill.
+pub fn settle_fill(ctx: Context<SettleFill>, game_index: u32, filler: Pubkey) -> Result<()> {
+    let SettleFill {
+        wager_mint,
+        games_account,
+        game_fills,
+        profile,
+        host_profile,
+        season,
+        season_score,
+        ..
+    } = ctx.accounts;
+
+    let game = games_account.games.get(game_index as usize).ok_or(Error::GameNotFound)?;
+    require!(game.mint == wager_mint.mint, Error::InvalidMint);
+    require!(game.partial_fills, Error::PartialFillsNotAllowed);
+
+    let result = game.result.ok_or(Error::GameNotFinished)?;
+    require!(game.host_prediction == result, Error::FillNotLost);
+
+    let fill = game_fills.fills
+        .iter_mut()
+        .find(|fill| fill.player == filler && !fill.is_claimed)
+        .ok_or(Error::FillNotFound)?;
+    fill.is_claimed = true;
+
+    require!(host_profile.player == game.host, Error::InvalidProfile);
+    profile.record_loss().ok_or(Error::ArithmeticOverflow)?;
+    (host_profile.rating, profile.rating) = elo_ratings(host_profile.rating, profile.rating);
+
+    let season = match (wager_mint.season, season) {
+        (Some(season_id), Some(season)) if season.id == season_id => season,
+        (None, None) => {
+            return Ok(());
+        }
+        _ => {
+            return err!(Error::InvalidSeason);
+        }
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+    match (season.is_active(now), season_score) {
+        (true, Some(score)) => {
+            score.init(season.id, filler);
+            score.record_loss(fill.amount);
+            season.record(filler, score.net_winnings);
+        }
+        (false, None) => {}
+        (true, None) => {
+            return err!(Error::MissingSeasonAccounts);
+        }
+        (false, Some(_)) => {
+            return err!(Error::SeasonNotActive);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants::{ self, MAX_INTENTS_PER_PLAYER, MAX_QUEUED_INTENTS },
+    error::Error,
+    state::{
+        AllowlistEntry,
+        Balance,
+        Config,
+        Game,
+        GameMode,
+        Intent,
+        IntentQueue,
+        PlayerProfile,
+        Prices,
+        SolEscrow,
+        WagerMint,
+    },
+    utils::{ check_market_access, load_market_access, resize_account, Escrow },
+};
+
+#[derive(Clone, AnchorDeserialize, AnchorSerialize)]
+pub struct SubmitIntentArgs {
+    pub market: u16,
+    pub prediction: bool,
+    /// Index into `Config.threshold_tiers`.
+    pub tier: u8,
+    pub bet_size: u64,
+}
+
+/// Escrows the stake of a directional game intent and queues it for `match_intents`.
+#[derive(Accounts)]
+#[instruction(args: SubmitIntentArgs)]
+pub struct SubmitIntent<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(seeds = [constants::PRICES_SEED, &args.market.to_le_bytes()], bump)]
+    pub prices_account: Box<Account<'info, Prices>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::INTENTS_SEED, &args.market.to_le_bytes()],
+        bump,
+        payer = payer,
+        space = IntentQueue::space(0)
+    )]
+    pub intent_queue: Box<Account<'info, IntentQueue>>,
+
+    /// Token accounts are omitted for native SOL wagers, which go to the SOL escrow instead.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_account
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Pays the stake from the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
+
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    /// CHECK: `MarketAccess` of the market, uninitialized for markets open to everyone.
+    #[account(seeds = [constants::MARKET_ACCESS_SEED, &args.market.to_le_bytes()], bump)]
+    pub market_access: UncheckedAccount<'info>,
+
+    /// Token account of the gate mint, for holders in gated markets.
+    pub access_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub allowlist_entry: Option<Box<Account<'info, AllowlistEntry>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn submit_intent(ctx: Context<SubmitIntent>, args: SubmitIntentArgs) -> Result<()> {
+    let SubmitIntent {
+        player,
+        payer,
+        config,
+        mint,
+        wager_mint,
+        prices_account: prices,
+        intent_queue,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        balance,
+        token_program,
+        system_program,
+        profile,
+        market_access,
+        access_token_account,
+        allowlist_entry,
+    } = ctx.accounts;
+
+    let SubmitIntentArgs { market, prediction, tier, bet_size } = args;
+
+    require!(wager_mint.is_enabled, Error::MintDisabled);
+    require!(wager_mint.accepts(bet_size), Error::InvalidBetSize);
+
+    if let Some(market_access) = load_market_access(market_access)? {
+        check_market_access(
+            &market_access,
+            player.key(),
+            access_token_account.as_deref(),
+            allowlist_entry.as_deref()
+        )?;
+    }
+    require!(intent_queue.intents.len() < MAX_QUEUED_INTENTS, Error::IntentQueueFull);
+    require!(
+        intent_queue.intents_of(player.key()) < MAX_INTENTS_PER_PLAYER,
+        Error::PlayerIntentsLimit
+    );
+
+    let thresholds = config.threshold_tier(tier).ok_or(Error::InvalidThresholdTier)?;
+
+    // Priced like a game hosted on the intent's side
+    let mut game = Game::new(
+        player.key(),
+        prediction,
+        mint.key(),
+        market,
+        prices.last_index(),
+        GameMode::Directional,
+        None
+    );
+    game.set_tier(tier, thresholds);
+    let (stake, _) = game.stakes(bet_size).ok_or(Error::ArithmeticOverflow)?;
+
+    let received = match balance {
+        Some(balance) => {
+            balance.debit(stake).ok_or(Error::InsufficientBalance)?;
+            stake
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            let received = escrow.deposit(player, stake, system_program)?;
+
+            wager_mint.lock(received).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+            received
+        }
+    };
+
+    profile.init(player.key());
+    profile.record_wager(received).ok_or(Error::ArithmeticOverflow)?;
+
+    // The anti-spam deposit waits in the player's profile until the intent is cancelled or matched
+    if config.spam_deposit > 0 {
+        system_program::transfer(
+            CpiContext::new(system_program.to_account_info(), system_program::Transfer {
+                from: player.to_account_info(),
+                to: profile.to_account_info(),
+            }),
+            config.spam_deposit
+        )?;
+    }
+
+    let required_space = IntentQueue::space(intent_queue.intents.len() + 1);
+    let current_space = intent_queue.to_account_info().data_len();
+    if current_space < required_space {
+        resize_account(intent_queue, payer, system_program, required_space - current_space)?;
+    }
+
+    let id = intent_queue.next_id;
+    intent_queue.market = market;
+    intent_queue.next_id = id.checked_add(1).ok_or(Error::ArithmeticOverflow)?;
+    intent_queue.intents.push(Intent {
+        id,
+        player: player.key(),
+        prediction,
+        mint: mint.key(),
+        bet_size,
+        tier,
+        thresholds,
+        amount: received,
+        price_index: prices.last_index(),
+        spam_deposit: config.spam_deposit,
+    });
+
+    Ok(())
+}
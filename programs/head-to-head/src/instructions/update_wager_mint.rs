@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, error::Error, state::{ Config, WagerMint } };
+
+use super::WagerMintArgs;
+
+#[derive(Accounts)]
+pub struct UpdateWagerMint<'info> {
+    #[account(address = config.admin @ Error::AdminOnly)]
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [constants::CONFIG_SEED], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut, seeds = [constants::WAGER_MINT_SEED, wager_mint.mint.as_ref()], bump)]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+}
+
+pub fn update_wager_mint(
+    ctx: Context<UpdateWagerMint>,
+    args: WagerMintArgs,
+    is_enabled: bool
+) -> Result<()> {
+    let wager_mint = &mut ctx.accounts.wager_mint;
+
+    require!(args.is_valid(), Error::InvalidBetLimits);
+
+    wager_mint.min_bet_size = args.min_bet_size;
+    wager_mint.max_bet_size = args.max_bet_size;
+    wager_mint.is_enabled = is_enabled;
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+
+use crate::{
+    constants,
+    error::Error,
+    state::{ Balance, SolEscrow, WagerMint },
+    utils::Escrow,
+};
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::BALANCE_SEED, player.key().as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = player,
+        has_one = mint
+    )]
+    pub balance: Box<Account<'info, Balance>>,
+
+    /// Token accounts are omitted for native SOL, which is paid from the SOL escrow.
+    #[account(
+        mut,
+        constraint = player_token_account.mint == mint.key(),
+        constraint = player_token_account.owner == player.key()
+    )]
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+    )]
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+    let Withdraw {
+        player,
+        mint,
+        wager_mint,
+        balance,
+        player_token_account,
+        vault_account,
+        sol_escrow,
+        token_program,
+    } = ctx.accounts;
+
+    balance.debit(amount).ok_or(Error::InsufficientBalance)?;
+
+    let mut escrow = Escrow::new(
+        mint,
+        player_token_account.as_deref(),
+        vault_account.as_deref_mut(),
+        sol_escrow.as_deref(),
+        token_program.as_ref()
+    )?;
+    escrow.pay_out(player, amount, ctx.bumps.vault_account)?;
+
+    wager_mint.release(amount).ok_or(Error::ArithmeticOverflow)?;
+    escrow.check_solvency(wager_mint)?;
+
+    Ok(())
+}
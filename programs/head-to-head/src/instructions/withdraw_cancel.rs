@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants, error::Error, state::Games };
+
+#[derive(Accounts)]
+pub struct WithdrawCancel<'info> {
+    pub player: Signer<'info>,
+
+    #[account(mut, seeds = [constants::GAMES_SEED], bump)]
+    pub games_account: Box<Account<'info, Games>>,
+}
+
+/// Takes back the player's proposal to cancel a game, so the other player can no longer agree
+/// to it.
+pub fn withdraw_cancel(ctx: Context<WithdrawCancel>, game_index: u32) -> Result<()> {
+    let WithdrawCancel { player, games_account } = ctx.accounts;
+
+    require!((game_index as usize) < games_account.games.len(), Error::GameNotFound);
+
+    let game = &mut games_account.games[game_index as usize];
+    require!(!game.is_closed, Error::GameAlreadyClosed);
+    require!(game.withdraw_cancel(player.key()), Error::NoCancelProposal);
+
+    Ok(())
+}
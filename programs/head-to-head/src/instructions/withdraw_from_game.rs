@@ -1,9 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{ Token, TokenAccount };
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
 use crate::{
-    constants::{ self, VAULT_SEED },
-    state::{ Config, Games },
-    utils::transfer_tokens,
+    constants,
+    state::{ Balance, Config, Games, PlayerProfile, SolEscrow, WagerMint },
+    utils::{ refund_spam_deposit, Escrow },
     error::Error,
 };
 
@@ -12,28 +12,59 @@ pub struct WithdrawFromGame<'info> {
     #[account(mut)]
     pub player: Signer<'info>,
 
+    /// Pays the rent of the accounts the instruction creates or grows.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
     #[account(seeds = [constants::CONFIG_SEED], bump)]
     pub config: Box<Account<'info, Config>>,
 
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [constants::WAGER_MINT_SEED, mint.key().as_ref()],
+        bump,
+        has_one = mint
+    )]
+    pub wager_mint: Box<Account<'info, WagerMint>>,
+
+    /// Token accounts are omitted for native SOL wagers, which are paid from the SOL escrow.
     #[account(
         mut,
-        constraint = player_token_account.mint == config.mint,
+        constraint = player_token_account.mint == mint.key(),
         constraint = player_token_account.owner == player.key()
     )]
-    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    pub player_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
         mut,
-        seeds = [constants::VAULT_SEED],
+        seeds = [constants::VAULT_SEED, mint.key().as_ref()],
         bump,
-        token::mint = config.mint,
+        token::mint = mint,
     )]
-    pub vault_account: Box<Account<'info, TokenAccount>>,
+    pub vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub sol_escrow: Option<Box<Account<'info, SolEscrow>>>,
+
+    /// Credits the payout to the player's balance instead of their wallet.
+    #[account(mut, has_one = player, has_one = mint)]
+    pub balance: Option<Box<Account<'info, Balance>>>,
 
     #[account(mut, seeds = [constants::GAMES_SEED], bump)]
     pub games_account: Box<Account<'info, Games>>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        init_if_needed,
+        seeds = [constants::PROFILE_SEED, player.key().as_ref()],
+        bump,
+        payer = payer,
+        space = PlayerProfile::len()
+    )]
+    pub profile: Box<Account<'info, PlayerProfile>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     pub system_program: Program<'info, System>,
 }
 
@@ -43,28 +74,56 @@ pub fn withdraw_from_game(ctx: Context<WithdrawFromGame>, game_index: u32) -> Re
         games_account,
         player_token_account,
         vault_account,
+        sol_escrow,
+        balance,
         token_program,
+        mint,
+        wager_mint,
+        profile,
         ..
     } = ctx.accounts;
 
     require!((game_index as usize) < games_account.games.len(), Error::GameNotFound);
 
     let game = &mut games_account.games[game_index as usize];
+    require!(game.mint == mint.key(), Error::InvalidMint);
 
     require!(game.host == player.key(), Error::UnauthorizedWithdrawal);
-    require!(game.is_closed == false, Error::GameAlreadyClosed);
-    require!(game.opponent.is_none(), Error::WithdrawalNotAllowed);
-
-    transfer_tokens(
-        vault_account.to_account_info(),
-        player_token_account.to_account_info(),
-        vault_account.to_account_info(),
-        game.amount,
-        token_program.to_account_info(),
-        Some(&[&[VAULT_SEED, &[ctx.bumps.vault_account]]])
-    )?;
-
-    game.close();
+
+    // Started partial fill games refund the unmatched host stake, even once settled
+    let (refund, is_cancelled) = if game.partial_fills && game.is_started() {
+        let remainder = game.freeze_fills().ok_or(Error::ArithmeticOverflow)?;
+        require!(remainder > 0, Error::WithdrawalNotAllowed);
+        (remainder, false)
+    } else {
+        require!(!game.is_closed, Error::GameAlreadyClosed);
+        require!(!game.is_started(), Error::WithdrawalNotAllowed);
+        game.close();
+        (game.amount, true)
+    };
+    refund_spam_deposit(game, Some(player.as_ref()), Some(profile))?;
+
+    profile.init(player.key());
+    profile.record_withdrawal(refund, is_cancelled).ok_or(Error::ArithmeticOverflow)?;
+
+    match balance {
+        Some(balance) => {
+            balance.credit(refund).ok_or(Error::ArithmeticOverflow)?;
+        }
+        None => {
+            let mut escrow = Escrow::new(
+                mint,
+                player_token_account.as_deref(),
+                vault_account.as_deref_mut(),
+                sol_escrow.as_deref(),
+                token_program.as_ref()
+            )?;
+            escrow.pay_out(player, refund, ctx.bumps.vault_account)?;
+
+            wager_mint.release(refund).ok_or(Error::ArithmeticOverflow)?;
+            escrow.check_solvency(wager_mint)?;
+        }
+    }
 
     Ok(())
 }
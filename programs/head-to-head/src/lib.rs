@@ -35,26 +35,218 @@ pub mod head_to_head {
         instructions::initialize_prices(ctx, args)
     }
 
-    pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
-        instructions::initialize_vault(ctx)
+    pub fn initialize_vault(ctx: Context<InitializeVault>, args: WagerMintArgs) -> Result<()> {
+        instructions::initialize_vault(ctx, args)
     }
 
-    pub fn add_price(ctx: Context<AddPrice>, price: u64) -> Result<()> {
-        instructions::add_price(ctx, price)
+    pub fn initialize_sol_escrow(
+        ctx: Context<InitializeSolEscrow>,
+        args: WagerMintArgs
+    ) -> Result<()> {
+        instructions::initialize_sol_escrow(ctx, args)
+    }
+
+    pub fn update_wager_mint(
+        ctx: Context<UpdateWagerMint>,
+        args: WagerMintArgs,
+        is_enabled: bool
+    ) -> Result<()> {
+        instructions::update_wager_mint(ctx, args, is_enabled)
+    }
+
+    pub fn set_threshold_tiers(
+        ctx: Context<SetThresholdTiers>,
+        tiers: Vec<state::ThresholdTier>
+    ) -> Result<()> {
+        instructions::set_threshold_tiers(ctx, tiers)
+    }
+
+    pub fn set_protocol_fee(
+        ctx: Context<SetProtocolFee>,
+        fee_bps: u16,
+        referral_share_bps: u16
+    ) -> Result<()> {
+        instructions::set_protocol_fee(ctx, fee_bps, referral_share_bps)
+    }
+
+    pub fn set_spam_limits(
+        ctx: Context<SetSpamLimits>,
+        max_open_games_per_player: u16,
+        spam_deposit: u64
+    ) -> Result<()> {
+        instructions::set_spam_limits(ctx, max_open_games_per_player, spam_deposit)
+    }
+
+    pub fn set_market_access(
+        ctx: Context<SetMarketAccess>,
+        market: u16,
+        gate_mint: Option<Pubkey>
+    ) -> Result<()> {
+        instructions::set_market_access(ctx, market, gate_mint)
+    }
+
+    pub fn remove_market_access(ctx: Context<RemoveMarketAccess>, market: u16) -> Result<()> {
+        instructions::remove_market_access(ctx, market)
+    }
+
+    pub fn add_to_allowlist(
+        ctx: Context<AddToAllowlist>,
+        market: u16,
+        player: Pubkey
+    ) -> Result<()> {
+        instructions::add_to_allowlist(ctx, market, player)
     }
-    pub fn create_game(ctx: Context<CreateGame>, prediction: bool) -> Result<()> {
-        instructions::create_game(ctx, prediction)
+
+    pub fn remove_from_allowlist(
+        ctx: Context<RemoveFromAllowlist>,
+        market: u16,
+        player: Pubkey
+    ) -> Result<()> {
+        instructions::remove_from_allowlist(ctx, market, player)
+    }
+
+    pub fn create_season(
+        ctx: Context<CreateSeason>,
+        season_id: u32,
+        start_time: i64,
+        end_time: i64
+    ) -> Result<()> {
+        instructions::create_season(ctx, season_id, start_time, end_time)
+    }
+
+    pub fn close_season(ctx: Context<CloseSeason>, season_id: u32) -> Result<()> {
+        instructions::close_season(ctx, season_id)
+    }
+
+    pub fn reconcile_vault(ctx: Context<ReconcileVault>) -> Result<state::VaultReport> {
+        instructions::reconcile_vault(ctx)
+    }
+
+    pub fn add_price(ctx: Context<AddPrice>, market: u16, price: u64) -> Result<()> {
+        instructions::add_price(ctx, market, price)
+    }
+
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        session_key: Pubkey,
+        expires_at: i64,
+        spending_cap: u64
+    ) -> Result<()> {
+        instructions::create_session(ctx, session_key, expires_at, spending_cap)
+    }
+
+    pub fn revoke_session(ctx: Context<RevokeSession>, session_key: Pubkey) -> Result<()> {
+        instructions::revoke_session(ctx, session_key)
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        instructions::deposit(ctx, amount)
+    }
+
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        instructions::withdraw(ctx, amount)
+    }
+
+    pub fn create_game(ctx: Context<CreateGame>, args: CreateGameArgs) -> Result<()> {
+        instructions::create_game(ctx, args)
     }
 
     pub fn join_game(ctx: Context<JoinGame>, game_index: u32) -> Result<()> {
         instructions::join_game(ctx, game_index)
     }
 
+    pub fn quick_join(ctx: Context<QuickJoin>, args: QuickJoinArgs) -> Result<()> {
+        instructions::quick_join(ctx, args)
+    }
+
+    pub fn fill_game(ctx: Context<FillGame>, game_index: u32, amount: u64) -> Result<()> {
+        instructions::fill_game(ctx, game_index, amount)
+    }
+
     pub fn claim_winnings(ctx: Context<ClaimWinnings>, game_index: u32) -> Result<()> {
         instructions::claim_winnings(ctx, game_index)
     }
 
+    pub fn claim_fill(ctx: Context<ClaimFill>, game_index: u32) -> Result<()> {
+        instructions::claim_fill(ctx, game_index)
+    }
+
+    pub fn settle_fill(ctx: Context<SettleFill>, game_index: u32, filler: Pubkey) -> Result<()> {
+        instructions::settle_fill(ctx, game_index, filler)
+    }
+
+    pub fn claim_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>,
+        game_indices: Vec<u32>
+    ) -> Result<()> {
+        instructions::claim_many(ctx, game_indices)
+    }
+
+    pub fn submit_intent(ctx: Context<SubmitIntent>, args: SubmitIntentArgs) -> Result<()> {
+        instructions::submit_intent(ctx, args)
+    }
+
+    pub fn cancel_intent(ctx: Context<CancelIntent>, market: u16, intent_id: u64) -> Result<()> {
+        instructions::cancel_intent(ctx, market, intent_id)
+    }
+
+    pub fn match_intents(ctx: Context<MatchIntents>, market: u16, max_matches: u8) -> Result<()> {
+        instructions::match_intents(ctx, market, max_matches)
+    }
+
+    pub fn register_referrer(ctx: Context<RegisterReferrer>, referrer: Pubkey) -> Result<()> {
+        instructions::register_referrer(ctx, referrer)
+    }
+
+    pub fn open_referral_rewards(
+        ctx: Context<OpenReferralRewards>,
+        referrer: Pubkey
+    ) -> Result<()> {
+        instructions::open_referral_rewards(ctx, referrer)
+    }
+
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        instructions::claim_referral_rewards(ctx)
+    }
+
+    pub fn enter_season(ctx: Context<EnterSeason>, season_id: u32) -> Result<()> {
+        instructions::enter_season(ctx, season_id)
+    }
+
+    pub fn claim_season_prize(ctx: Context<ClaimSeasonPrize>, season_id: u32) -> Result<()> {
+        instructions::claim_season_prize(ctx, season_id)
+    }
+
     pub fn withdraw_from_game(ctx: Context<WithdrawFromGame>, game_index: u32) -> Result<()> {
         instructions::withdraw_from_game(ctx, game_index)
     }
+
+    pub fn rematch(
+        ctx: Context<Rematch>,
+        game_index: u32,
+        swap_predictions: bool
+    ) -> Result<()> {
+        instructions::rematch(ctx, game_index, swap_predictions)
+    }
+
+    pub fn place_side_bet(
+        ctx: Context<PlaceSideBet>,
+        game_index: u32,
+        backs_host: bool,
+        amount: u64
+    ) -> Result<()> {
+        instructions::place_side_bet(ctx, game_index, backs_host, amount)
+    }
+
+    pub fn claim_side_bet(ctx: Context<ClaimSideBet>, game_index: u32) -> Result<()> {
+        instructions::claim_side_bet(ctx, game_index)
+    }
+
+    pub fn cancel_game(ctx: Context<CancelGame>, game_index: u32) -> Result<()> {
+        instructions::cancel_game(ctx, game_index)
+    }
+
+    pub fn withdraw_cancel(ctx: Context<WithdrawCancel>, game_index: u32) -> Result<()> {
+        instructions::withdraw_cancel(ctx, game_index)
+    }
 }
@@ -1,20 +1,511 @@
 use anchor_lang::prelude::*;
 
-use crate::utils::check_price_fluctuation;
+use crate::constants::{
+    BIG_BUCKET_ODDS,
+    FEE_BPS_DENOMINATOR,
+    INITIAL_RATING,
+    MAX_LEADERBOARD_SIZE,
+    MAX_OPEN_GAMES,
+    MAX_THRESHOLD_TIERS,
+    MIN_JOIN_DURATION,
+    REMATCH_WINDOW,
+    SMALL_BUCKET_ODDS,
+};
+use crate::utils::{
+    check_price_buckets,
+    check_price_fluctuation,
+    check_price_range,
+    check_price_thresholds,
+    check_relative_performance,
+    split_stakes,
+    PriceSeries,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ThresholdTier {
+    pub up_threshold_percent: u16,
+    pub down_threshold_percent: u16,
+    pub join_threshold_percent: u16,
+}
+
+impl ThresholdTier {
+    pub fn is_valid(&self) -> bool {
+        self.join_threshold_percent < self.up_threshold_percent &&
+            self.join_threshold_percent < self.down_threshold_percent
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.up_threshold_percent == self.down_threshold_percent
+    }
+
+    pub fn is_valid_list(tiers: &[ThresholdTier]) -> bool {
+        !tiers.is_empty() &&
+            tiers.len() <= MAX_THRESHOLD_TIERS &&
+            tiers.iter().all(ThresholdTier::is_valid)
+    }
+}
 
 #[account]
 pub struct Config {
     pub admin: Pubkey,
-    pub mint: Pubkey,
-    pub bet_size: u64,
-    pub win_threshold_percent: u16,
-    pub join_threshold_percent: u16,
     pub threshold_decimals: u8,
+    /// Protocol fee taken from payouts, in basis points.
+    pub fee_bps: u16,
+    /// Share of the protocol fee credited to the referrer of each referred player, in basis points.
+    pub referral_share_bps: u16,
+    /// Open challenges a player may have waiting for an opponent, 0 for no limit.
+    pub max_open_games_per_player: u16,
+    /// Refundable lamports a host leaves with each open challenge, 0 for none.
+    pub spam_deposit: u64,
+    pub threshold_tiers: Vec<ThresholdTier>,
 }
 
 impl Config {
     pub fn len() -> usize {
-        8 + 32 + 32 + 8 + 2 + 2 + 1
+        8 + 32 + 1 + 2 + 2 + 2 + 8 + 4 + MAX_THRESHOLD_TIERS * (2 + 2 + 2)
+    }
+
+    pub fn threshold_tier(&self, tier: u8) -> Option<ThresholdTier> {
+        self.threshold_tiers.get(tier as usize).copied()
+    }
+}
+
+/// Registry entry of a mint accepted for wagers, its vault is seeded by the mint.
+#[account]
+pub struct WagerMint {
+    pub mint: Pubkey,
+    pub min_bet_size: u64,
+    pub max_bet_size: u64,
+    /// Disabled mints can't be used for new games, running games still settle.
+    pub is_enabled: bool,
+    /// Stakes of open and active games and player balances the vault owes to players.
+    pub locked_amount: u64,
+    /// Protocol fees kept in the vault, part of `locked_amount` until paid out as season prizes.
+    pub fees: u64,
+    /// Season of the mint that is not closed yet, settlements need its accounts until it is.
+    pub season: Option<u32>,
+}
+
+impl WagerMint {
+    pub fn len() -> usize {
+        8 + 32 + 8 + 8 + 1 + 8 + 8 + 5
+    }
+
+    pub fn accepts(&self, bet_size: u64) -> bool {
+        (self.min_bet_size..=self.max_bet_size).contains(&bet_size)
+    }
+
+    pub fn lock(&mut self, amount: u64) -> Option<()> {
+        self.locked_amount = self.locked_amount.checked_add(amount)?;
+        Some(())
+    }
+
+    pub fn release(&mut self, amount: u64) -> Option<()> {
+        self.locked_amount = self.locked_amount.checked_sub(amount)?;
+        Some(())
+    }
+
+    pub fn is_covered_by(&self, balance: u64) -> bool {
+        balance >= self.locked_amount
+    }
+
+    /// Keeps the protocol fee of `payout` and returns what is left for the player.
+    pub fn take_fee(&mut self, payout: u64, fee_bps: u16) -> Option<u64> {
+        let fee = (payout as u128)
+            .checked_mul(fee_bps as u128)?
+            .checked_div(FEE_BPS_DENOMINATOR as u128)? as u64;
+        self.fees = self.fees.checked_add(fee)?;
+        payout.checked_sub(fee)
+    }
+
+    /// Moves the referral share of `fee` out of the protocol fees and returns it.
+    pub fn share_fee(&mut self, fee: u64, share_bps: u16) -> Option<u64> {
+        let share = (fee as u128)
+            .checked_mul(share_bps as u128)?
+            .checked_div(FEE_BPS_DENOMINATOR as u128)? as u64;
+        self.fees = self.fees.checked_sub(share)?;
+        Some(share)
+    }
+}
+
+/// Vault balance of a mint against the stakes locked in its games.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct VaultReport {
+    pub mint: Pubkey,
+    pub balance: u64,
+    pub locked_amount: u64,
+    pub surplus: u64,
+    pub deficit: u64,
+}
+
+impl VaultReport {
+    pub fn new(wager_mint: &WagerMint, balance: u64) -> Self {
+        Self {
+            mint: wager_mint.mint,
+            balance,
+            locked_amount: wager_mint.locked_amount,
+            surplus: balance.saturating_sub(wager_mint.locked_amount),
+            deficit: wager_mint.locked_amount.saturating_sub(balance),
+        }
+    }
+}
+
+/// Funds a player keeps in the vault of a mint to play games without a transfer each time.
+#[account]
+pub struct Balance {
+    pub player: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+impl Balance {
+    pub fn len() -> usize {
+        8 + 32 + 32 + 8
+    }
+
+    pub fn credit(&mut self, amount: u64) -> Option<()> {
+        self.amount = self.amount.checked_add(amount)?;
+        Some(())
+    }
+
+    pub fn debit(&mut self, amount: u64) -> Option<()> {
+        self.amount = self.amount.checked_sub(amount)?;
+        Some(())
+    }
+}
+
+/// Lifetime stats of a player, created on their first wager.
+#[account]
+pub struct PlayerProfile {
+    pub player: Pubkey,
+    /// Player who referred this one, set once with `register_referrer`.
+    pub referrer: Option<Pubkey>,
+    /// Games the player staked in, less the ones withdrawn before an opponent joined.
+    pub games_played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub withdrawals: u32,
+    /// Stakes committed to games, less refunds.
+    pub total_wagered: u64,
+    /// Payouts of won games, including the player's own stake.
+    pub total_won: u64,
+    /// Consecutive wins up to the last settled game.
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// Elo rating, updated whenever a game or a fill settles against a single counterparty.
+    pub rating: u16,
+}
+
+impl PlayerProfile {
+    pub fn len() -> usize {
+        8 + 32 + 33 + 4 + 4 + 4 + 4 + 8 + 8 + 4 + 4 + 2
+    }
+
+    /// Sets up a profile created by `init_if_needed`, starting it at the initial rating.
+    pub fn init(&mut self, player: Pubkey) {
+        self.player = player;
+        if self.rating == 0 {
+            self.rating = INITIAL_RATING;
+        }
+    }
+
+    pub fn record_wager(&mut self, amount: u64) -> Option<()> {
+        self.games_played = self.games_played.checked_add(1)?;
+        self.total_wagered = self.total_wagered.checked_add(amount)?;
+        Some(())
+    }
+
+    /// Refund of `amount`, `is_cancelled` when the whole game is withdrawn. Profiles created
+    /// after the wager saturate at zero.
+    pub fn record_withdrawal(&mut self, amount: u64, is_cancelled: bool) -> Option<()> {
+        self.total_wagered = self.total_wagered.saturating_sub(amount);
+        if is_cancelled {
+            self.games_played = self.games_played.saturating_sub(1);
+            self.withdrawals = self.withdrawals.checked_add(1)?;
+        }
+        Some(())
+    }
+
+    pub fn record_win(&mut self, payout: u64) -> Option<()> {
+        self.wins = self.wins.checked_add(1)?;
+        self.total_won = self.total_won.checked_add(payout)?;
+        self.current_streak = self.current_streak.checked_add(1)?;
+        self.best_streak = self.best_streak.max(self.current_streak);
+        Some(())
+    }
+
+    pub fn record_loss(&mut self) -> Option<()> {
+        self.losses = self.losses.checked_add(1)?;
+        self.current_streak = 0;
+        Some(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LeaderboardEntry {
+    pub player: Pubkey,
+    pub net_winnings: u64,
+    /// Share of the prize pool, set when the season is closed.
+    pub prize: u64,
+    pub is_claimed: bool,
+}
+
+impl LeaderboardEntry {
+    pub const SIZE: usize = 32 + 8 + 8 + 1;
+}
+
+/// Competition over the games of one mint claimed between `start_time` and `end_time`, with
+/// the players of the highest net winnings on its leaderboard.
+#[account]
+pub struct Season {
+    pub id: u32,
+    pub mint: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// Protocol fees kept before the season and from the games settled while it is active,
+    /// distributed to the leaderboard when it is closed.
+    pub prize_pool: u64,
+    pub is_closed: bool,
+    /// Highest net winnings first.
+    pub leaderboard: Vec<LeaderboardEntry>,
+}
+
+impl Season {
+    pub fn len() -> usize {
+        8 + 4 + 32 + 8 + 8 + 8 + 1 + 4 + MAX_LEADERBOARD_SIZE * LeaderboardEntry::SIZE
+    }
+
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.is_closed && (self.start_time..self.end_time).contains(&now)
+    }
+
+    /// Ranks `player` with their season total `net_winnings`, evicting the last entry when the
+    /// leaderboard is full and the player beats it. Players without net winnings are not added.
+    pub fn record(&mut self, player: Pubkey, net_winnings: u64) {
+        match self.leaderboard.iter_mut().find(|entry| entry.player == player) {
+            Some(entry) => {
+                entry.net_winnings = net_winnings;
+            }
+            None if net_winnings == 0 => {}
+            None => {
+                let entry = LeaderboardEntry { player, net_winnings, prize: 0, is_claimed: false };
+                if self.leaderboard.len() < MAX_LEADERBOARD_SIZE {
+                    self.leaderboard.push(entry);
+                } else if
+                    let Some(last) = self.leaderboard
+                        .last_mut()
+                        .filter(|last| last.net_winnings < net_winnings)
+                {
+                    *last = entry;
+                }
+            }
+        }
+        self.leaderboard.sort_by_key(|entry| std::cmp::Reverse(entry.net_winnings));
+    }
+
+    /// Splits `prize_pool` between the leaderboard in proportion to net winnings and returns the
+    /// amount handed out, rounding dust stays with the protocol fees.
+    pub fn distribute(&mut self, prize_pool: u64) -> Option<u64> {
+        let total = self.leaderboard
+            .iter()
+            .try_fold(0u128, |total, entry| total.checked_add(entry.net_winnings as u128))?;
+        if total == 0 {
+            return Some(0);
+        }
+
+        let mut distributed: u64 = 0;
+        for entry in self.leaderboard.iter_mut() {
+            entry.prize = (prize_pool as u128)
+                .checked_mul(entry.net_winnings as u128)?
+                .checked_div(total)? as u64;
+            distributed = distributed.checked_add(entry.prize)?;
+        }
+        self.prize_pool = distributed;
+
+        Some(distributed)
+    }
+}
+
+/// Net winnings of a player over a season, entered with `enter_season`.
+#[account]
+pub struct SeasonScore {
+    pub season: u32,
+    pub player: Pubkey,
+    /// Winnings less losses, floored at zero.
+    pub net_winnings: u64,
+}
+
+impl SeasonScore {
+    pub fn len() -> usize {
+        8 + 4 + 32 + 8
+    }
+
+    /// Sets up a score created by `init_if_needed` for a loser who hadn't entered the season.
+    pub fn init(&mut self, season: u32, player: Pubkey) {
+        if self.player == Pubkey::default() {
+            self.season = season;
+            self.player = player;
+        }
+    }
+
+    pub fn record_win(&mut self, winnings: u64) -> Option<()> {
+        self.net_winnings = self.net_winnings.checked_add(winnings)?;
+        Some(())
+    }
+
+    pub fn record_loss(&mut self, loss: u64) {
+        self.net_winnings = self.net_winnings.saturating_sub(loss);
+    }
+}
+
+/// Referral rewards of a player in one mint, kept in its vault until claimed.
+#[account]
+pub struct ReferralRewards {
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+impl ReferralRewards {
+    pub fn len() -> usize {
+        8 + 32 + 32 + 8
+    }
+}
+
+/// Restricts a market to holders of `gate_mint` and players on its allowlist. Markets without
+/// one are open to everyone.
+#[account]
+pub struct MarketAccess {
+    pub market: u16,
+    /// Any positive balance of this mint grants access, `None` for allowlist only markets.
+    pub gate_mint: Option<Pubkey>,
+}
+
+impl MarketAccess {
+    pub fn len() -> usize {
+        8 + 2 + 33
+    }
+}
+
+/// Allowlist membership of a player in a gated market.
+#[account]
+pub struct AllowlistEntry {
+    pub market: u16,
+    pub player: Pubkey,
+}
+
+impl AllowlistEntry {
+    pub fn len() -> usize {
+        8 + 2 + 32
+    }
+}
+
+/// Parimutuel pool of spectators backing the host or the opponent of a joined game, one account
+/// per game index.
+#[account]
+pub struct SidePool {
+    pub game_index: u32,
+    pub mint: Pubkey,
+    pub host_total: u64,
+    pub opponent_total: u64,
+    /// Whether the host won, fixed by the first claim once the game is decided. Stays `None` for
+    /// cancelled games, whose backers get their stake back.
+    pub host_won: Option<bool>,
+}
+
+impl SidePool {
+    pub fn len() -> usize {
+        8 + 4 + 32 + 8 + 8 + (1 + 1)
+    }
+
+    pub fn back(&mut self, backs_host: bool, amount: u64) -> Option<()> {
+        let total = if backs_host { &mut self.host_total } else { &mut self.opponent_total };
+        *total = total.checked_add(amount)?;
+        Some(())
+    }
+
+    /// Payout of a bet of `amount` on a side: its share of the whole pool when it backed the
+    /// winner, its stake back when the game was cancelled or nobody backed the winner.
+    pub fn payout(&self, backs_host: bool, amount: u64) -> Option<u64> {
+        let Some(host_won) = self.host_won else {
+            return Some(amount);
+        };
+
+        let winning_total = if host_won { self.host_total } else { self.opponent_total };
+        if winning_total == 0 {
+            return Some(amount);
+        }
+        if backs_host != host_won {
+            return Some(0);
+        }
+
+        let pool = self.host_total.checked_add(self.opponent_total)?;
+        let payout = ((amount as u128) * (pool as u128)) / (winning_total as u128);
+        u64::try_from(payout).ok()
+    }
+
+    /// Whether a bet on a side won anything over its stake, which needs the winning side to be
+    /// backed and the losing side to have stakes to share.
+    pub fn has_winnings(&self, backs_host: bool) -> bool {
+        let losing_total = if backs_host { self.opponent_total } else { self.host_total };
+        self.host_won == Some(backs_host) && losing_total > 0
+    }
+}
+
+/// Stake of a spectator on one side of a game, closed when claimed.
+#[account]
+pub struct SideBet {
+    pub game_index: u32,
+    pub backer: Pubkey,
+    pub backs_host: bool,
+    pub amount: u64,
+}
+
+impl SideBet {
+    pub fn len() -> usize {
+        8 + 4 + 32 + 1 + 8
+    }
+}
+
+/// Ephemeral key a player authorizes to sign `create_game`, `join_game` and `claim_winnings` on
+/// their behalf until `expires_at`, staking at most `spending_cap` in total.
+#[account]
+pub struct Session {
+    pub player: Pubkey,
+    pub session_key: Pubkey,
+    pub expires_at: i64,
+    pub spending_cap: u64,
+    /// Stakes placed with the session key so far.
+    pub spent: u64,
+}
+
+impl Session {
+    pub fn len() -> usize {
+        8 + 32 + 32 + 8 + 8 + 8
+    }
+
+    pub fn authorizes(&self, player: Pubkey, session_key: Pubkey, now: i64) -> bool {
+        self.player == player && self.session_key == session_key && now < self.expires_at
+    }
+
+    pub fn spend(&mut self, amount: u64) -> Option<()> {
+        let spent = self.spent.checked_add(amount)?;
+        if spent > self.spending_cap {
+            return None;
+        }
+        self.spent = spent;
+        Some(())
+    }
+}
+
+/// Holds the lamports of native SOL wagers, registered under the native mint. Only
+/// `initialize_sol_escrow` creates one, at a fixed PDA, so the account type identifies it.
+#[account]
+pub struct SolEscrow {}
+
+impl SolEscrow {
+    pub fn len() -> usize {
+        8
     }
 }
 
@@ -23,6 +514,28 @@ impl Config {
 pub struct Prices {
     pub prices: Vec<u64>,
     pub decimals: u8,
+    pub timestamps: Vec<i64>,
+    pub market: u16,
+}
+
+impl Prices {
+    pub fn last_index(&self) -> u32 {
+        (self.prices.len() as u32) - 1
+    }
+
+    pub fn series(&self, start_index: u32) -> PriceSeries<'_> {
+        PriceSeries {
+            prices: &self.prices,
+            timestamps: &self.timestamps,
+            start_index: start_index as usize,
+        }
+    }
+
+    /// Prices recorded strictly before `timestamp`.
+    pub fn prices_before(&self, timestamp: i64) -> &[u64] {
+        let end = self.timestamps.partition_point(|&recorded_at| recorded_at < timestamp);
+        &self.prices[..end]
+    }
 }
 
 #[account]
@@ -30,30 +543,333 @@ pub struct Games {
     pub games: Vec<Game>,
 }
 
+impl Games {
+    /// Games of `host` still waiting for an opponent.
+    pub fn open_games_of(&self, host: Pubkey) -> usize {
+        self.games
+            .iter()
+            .filter(|game| game.host == host && game.is_open())
+            .count()
+    }
+}
+
+/// Directional games of a market waiting for an opponent, oldest first. Entries of games that
+/// were joined or closed since are pruned lazily.
+#[account]
+pub struct OpenGames {
+    pub market: u16,
+    pub game_indices: Vec<u32>,
+}
+
+impl OpenGames {
+    pub fn len() -> usize {
+        8 + 2 + 4 + MAX_OPEN_GAMES * 4
+    }
+
+    pub fn prune(&mut self, games: &[Game]) {
+        self.game_indices.retain(|&index| games.get(index as usize).is_some_and(Game::is_open));
+    }
+
+    /// Indexes a new game of the market unless the index is still full after pruning. Games left
+    /// out can be joined directly, they are only missing from quick joins.
+    pub fn insert(&mut self, games: &[Game], market: u16, game_index: u32) {
+        self.prune(games);
+        self.market = market;
+        if self.game_indices.len() < MAX_OPEN_GAMES {
+            self.game_indices.push(game_index);
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Fill {
+    pub player: Pubkey,
+    /// Stake as received by the vault.
+    pub amount: u64,
+    /// Paid out to a winning filler with `claim_fill`, or recorded as a loss with `settle_fill`.
+    pub is_claimed: bool,
+}
+
+impl Fill {
+    pub const SIZE: usize = 32 + 8 + 1;
+}
+
+/// Fills of a partial fill game, one entry per player.
+#[account]
+pub struct GameFills {
+    pub game_index: u32,
+    pub fills: Vec<Fill>,
+}
+
+impl GameFills {
+    pub fn space(fills: usize) -> usize {
+        8 + 4 + 4 + fills * Fill::SIZE
+    }
+}
+
+/// Stake escrowed by a player waiting to be matched against the opposite prediction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Intent {
+    pub id: u64,
+    pub player: Pubkey,
+    pub prediction: bool,
+    pub mint: Pubkey,
+    pub bet_size: u64,
+    pub tier: u8,
+    /// Snapshot of the tier at submission, the stake was priced with it.
+    pub thresholds: ThresholdTier,
+    /// Stake as received by the vault.
+    pub amount: u64,
+    /// Latest price when the intent was submitted. It only matches while the price stays within
+    /// the join threshold of it.
+    pub price_index: u32,
+    /// Anti-spam lamports held in the player's profile until the intent is cancelled or matched.
+    pub spam_deposit: u64,
+}
+
+impl Intent {
+    pub const SIZE: usize = 8 + 32 + 1 + 32 + 8 + 1 + (2 + 2 + 2) + 8 + 4 + 8;
+
+    /// Directional game hosted on the intent's side from `price_index`, on the intent's terms.
+    pub fn game(&self, market: u16, price_index: u32) -> Game {
+        let mut game = Game::new(
+            self.player,
+            self.prediction,
+            self.mint,
+            market,
+            price_index,
+            GameMode::Directional,
+            None
+        );
+        game.set_tier(self.tier, self.thresholds);
+        game
+    }
+
+    /// Intents match on opposite predictions of different players with the same terms.
+    pub fn matches(&self, other: &Intent) -> bool {
+        self.player != other.player &&
+            self.prediction != other.prediction &&
+            self.mint == other.mint &&
+            self.bet_size == other.bet_size &&
+            self.tier == other.tier &&
+            self.thresholds == other.thresholds
+    }
+}
+
+/// FIFO queue of directional game intents of a market.
+#[account]
+pub struct IntentQueue {
+    pub market: u16,
+    pub next_id: u64,
+    pub intents: Vec<Intent>,
+}
+
+impl IntentQueue {
+    pub fn space(intents: usize) -> usize {
+        8 + 2 + 8 + 4 + intents * Intent::SIZE
+    }
+
+    /// Intents of `player` waiting for a match.
+    pub fn intents_of(&self, player: Pubkey) -> usize {
+        self.intents
+            .iter()
+            .filter(|intent| intent.player == player)
+            .count()
+    }
+
+    /// Indices of the oldest intent with a match and its oldest match, among the intents flagged
+    /// in `is_live`.
+    pub fn next_match(&self, is_live: &[bool]) -> Option<(usize, usize)> {
+        self.intents
+            .iter()
+            .zip(is_live)
+            .enumerate()
+            .filter(|(_, (_, &live))| live)
+            .find_map(|(i, (intent, _))| {
+                let j = self.intents[i + 1..]
+                    .iter()
+                    .zip(&is_live[i + 1..])
+                    .position(|(other, &live)| live && intent.matches(other))?;
+                Some((i, i + 1 + j))
+            })
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    /// `host_prediction` is the direction (true = UP) of the first threshold crossing.
+    Directional,
+    /// `host_prediction` is whether the price breaks out of the band (true) or stays
+    /// within it (false) until `expires_at`.
+    Range,
+    /// `host_prediction` is whether the host backs `market` (true) or `opponent_market` (false)
+    /// to outperform the other one, by the threshold or at `expires_at` if set.
+    Relative,
+    /// The host backs `outcome` to be the bucket the price lands in when it first leaves the
+    /// small threshold band, the opponent backs every other bucket. `host_prediction` is true.
+    Buckets,
+}
+
+/// Bucket of the first price out of the small threshold band of a bucket game. Small buckets lie
+/// between the tier threshold and `BIG_BUCKET_MULTIPLIER` times it, big buckets beyond that.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    UpSmall,
+    UpBig,
+    DownSmall,
+    DownBig,
+}
+
+impl Outcome {
+    /// Opponent stake per unit of host stake for a host backing this bucket.
+    pub fn odds(&self) -> u64 {
+        match self {
+            Outcome::UpSmall | Outcome::DownSmall => SMALL_BUCKET_ODDS,
+            Outcome::UpBig | Outcome::DownBig => BIG_BUCKET_ODDS,
+        }
+    }
+}
+
 #[account]
 pub struct Game {
     pub host: Pubkey,
     pub opponent: Option<Pubkey>,
     pub host_prediction: bool,
+    /// Host stake, as received by the vault.
     pub amount: u64,
     pub price_index: u32,
     pub result: Option<bool>,
     pub is_closed: bool,
+    pub mode: GameMode,
+    pub expires_at: Option<i64>,
+    pub market: u16,
+    pub opponent_market: Option<u16>,
+    pub opponent_price_index: Option<u32>,
+    pub tier: u8,
+    pub thresholds: ThresholdTier,
+    /// Opponent stake: the deposit required to join, then the amount the vault received.
+    pub opponent_amount: u64,
+    pub mint: Pubkey,
+    /// Opponents fill `opponent_amount` in portions recorded in `GameFills` instead of joining.
+    pub partial_fills: bool,
+    /// Opponent stake received from fills so far.
+    pub filled_amount: u64,
+    /// Rating of the host when the game was created.
+    pub host_rating: u16,
+    /// Largest rating difference to the host an opponent may have, 0 when anyone can join.
+    pub rating_band: u16,
+    /// Anti-spam lamports held in the host's profile until the game is joined or withdrawn.
+    pub spam_deposit: u64,
+    /// Created in a market with a `MarketAccess` gate, opponents must pass it to join.
+    pub is_gated: bool,
+    /// Player that asked to cancel the running game, waiting for the other side to agree.
+    pub cancel_proposer: Option<Pubkey>,
+    /// Closed by mutual agreement, both stakes refunded.
+    pub is_cancelled: bool,
+    /// Previous opponent a rematch is reserved for until `reserved_until`.
+    pub reserved_for: Option<Pubkey>,
+    pub reserved_until: i64,
+    /// Bucket the host backs in bucket games, `None` in the other modes.
+    pub outcome: Option<Outcome>,
+    /// Seconds the game runs from its creation, `None` for games that don't expire.
+    pub duration: Option<i64>,
+    /// A rematch of the settled game was offered, it can't be rematched again.
+    pub is_rematched: bool,
 }
 
 impl Game {
-    pub fn new(host: Pubkey, host_prediction: bool, amount: u64, price_index: u32) -> Self {
+    pub fn new(
+        host: Pubkey,
+        host_prediction: bool,
+        mint: Pubkey,
+        market: u16,
+        price_index: u32,
+        mode: GameMode,
+        expires_at: Option<i64>
+    ) -> Self {
         Self {
             host,
             opponent: None,
             host_prediction,
-            amount,
+            amount: 0,
             price_index,
             result: None,
             is_closed: false,
+            mode,
+            expires_at,
+            market,
+            opponent_market: None,
+            opponent_price_index: None,
+            tier: 0,
+            thresholds: ThresholdTier {
+                up_threshold_percent: 0,
+                down_threshold_percent: 0,
+                join_threshold_percent: 0,
+            },
+            opponent_amount: 0,
+            mint,
+            partial_fills: false,
+            filled_amount: 0,
+            host_rating: 0,
+            rating_band: 0,
+            spam_deposit: 0,
+            is_gated: false,
+            cancel_proposer: None,
+            is_cancelled: false,
+            reserved_for: None,
+            reserved_until: 0,
+            outcome: None,
+            duration: None,
+            is_rematched: false,
         }
     }
 
+    /// Snapshots the tier thresholds so later tier edits don't affect running games.
+    pub fn set_tier(&mut self, tier: u8, thresholds: ThresholdTier) {
+        self.tier = tier;
+        self.thresholds = thresholds;
+    }
+
+    /// Backs `outcome` for the host of a bucket game, which wins when that bucket comes first.
+    pub fn set_outcome(&mut self, outcome: Outcome) {
+        self.host_prediction = true;
+        self.outcome = Some(outcome);
+    }
+
+    /// Host and opponent stakes for a game with the given base bet. Games with asymmetric
+    /// thresholds are priced by `split_stakes`, range games stake evenly and bucket games at the
+    /// odds of the host's bucket.
+    pub fn stakes(&self, bet_size: u64) -> Option<(u64, u64)> {
+        if self.mode == GameMode::Buckets {
+            return Some((bet_size, bet_size.checked_mul(self.outcome?.odds())?));
+        }
+        if self.mode == GameMode::Range || self.thresholds.is_symmetric() {
+            return Some((bet_size, bet_size));
+        }
+
+        let (up_stake, down_stake) = split_stakes(
+            bet_size,
+            self.thresholds.up_threshold_percent,
+            self.thresholds.down_threshold_percent
+        )?;
+
+        Some(if self.host_prediction { (up_stake, down_stake) } else { (down_stake, up_stake) })
+    }
+
+    pub fn set_stakes(&mut self, amount: u64, opponent_amount: u64) {
+        self.amount = amount;
+        self.opponent_amount = opponent_amount;
+    }
+
+    pub fn pot(&self) -> Option<u64> {
+        self.amount.checked_add(self.opponent_amount)
+    }
+
+    pub fn set_opponent_feed(&mut self, market: u16, price_index: u32) {
+        self.opponent_market = Some(market);
+        self.opponent_price_index = Some(price_index);
+    }
+
     pub fn set_result(&mut self, result: bool) {
         self.result = Some(result);
     }
@@ -62,23 +878,300 @@ impl Game {
         self.is_closed = true;
     }
 
-    pub fn join(&mut self, opponent: Pubkey) {
+    pub fn join(&mut self, opponent: Pubkey, opponent_amount: u64) {
         self.opponent = Some(opponent);
+        self.opponent_amount = opponent_amount;
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.is_closed && !self.is_started()
+    }
+
+    /// Player that lost the settled game, `None` when the fillers of a partial fill game lost,
+    /// whose losses are recorded with `settle_fill`.
+    pub fn loser(&self) -> Option<Pubkey> {
+        let result = self.result?;
+        if self.host_prediction == result { self.opponent } else { Some(self.host) }
+    }
+
+    /// Other player of a joined game, `None` when `player` is not part of it.
+    pub fn counterparty(&self, player: Pubkey) -> Option<Pubkey> {
+        let opponent = self.opponent?;
+        if player == self.host {
+            Some(opponent)
+        } else if player == opponent {
+            Some(self.host)
+        } else {
+            None
+        }
+    }
+
+    /// Records `player`'s cancel request, returns whether the other side had already made one.
+    pub fn propose_cancel(&mut self, player: Pubkey) -> bool {
+        let is_agreed = self.cancel_proposer.is_some_and(|proposer| proposer != player);
+        self.cancel_proposer = Some(player);
+        is_agreed
+    }
+
+    /// Withdraws the cancel proposal of `player`, false when they have none standing.
+    pub fn withdraw_cancel(&mut self, player: Pubkey) -> bool {
+        let is_proposer = self.cancel_proposer == Some(player);
+        if is_proposer {
+            self.cancel_proposer = None;
+        }
+        is_proposer
+    }
+
+    pub fn cancel(&mut self) {
+        self.is_cancelled = true;
+        self.close();
+    }
+
+    /// Prediction of `player` in the game, `None` when they are not part of it.
+    pub fn prediction_of(&self, player: Pubkey) -> Option<bool> {
+        if player == self.host {
+            Some(self.host_prediction)
+        } else if self.opponent == Some(player) {
+            Some(!self.host_prediction)
+        } else {
+            None
+        }
+    }
+
+    /// Rematch of this settled game hosted by `player`: same mode, markets, mint, thresholds and
+    /// duration, each side keeping its stake, with `player`'s prediction flipped when
+    /// `swap_predictions` is set. Relative games start the opponent feed at `opponent_price_index`.
+    pub fn rematch(
+        &self,
+        player: Pubkey,
+        swap_predictions: bool,
+        price_index: u32,
+        opponent_price_index: Option<u32>,
+        now: i64
+    ) -> Option<Self> {
+        // The host of a bucket game always backs a bucket, the sides can't trade places
+        if self.is_rematched || self.mode == GameMode::Buckets {
+            return None;
+        }
+
+        let opponent = self.counterparty(player)?;
+        let prediction = self.prediction_of(player)? != swap_predictions;
+        let expires_at = match self.duration {
+            Some(duration) => Some(now.checked_add(duration)?),
+            None => None,
+        };
+
+        let mut game = Self::new(
+            player,
+            prediction,
+            self.mint,
+            self.market,
+            price_index,
+            self.mode,
+            expires_at
+        );
+        game.set_tier(self.tier, self.thresholds);
+        game.duration = self.duration;
+        if let Some(opponent_market) = self.opponent_market {
+            game.set_opponent_feed(opponent_market, opponent_price_index?);
+        }
+
+        // Stakes follow the predictions, which asymmetric thresholds price differently
+        if prediction == self.host_prediction {
+            game.set_stakes(self.amount, self.opponent_amount);
+        } else {
+            game.set_stakes(self.opponent_amount, self.amount);
+        }
+
+        game.reserved_for = Some(opponent);
+        game.reserved_until = now.checked_add(REMATCH_WINDOW)?;
+        Some(game)
+    }
+
+    /// Whether `player` may join, rematches only take their reserved opponent until the window
+    /// ends.
+    pub fn accepts_opponent(&self, player: Pubkey, now: i64) -> bool {
+        !self.reserved_for.is_some_and(|opponent| opponent != player && now < self.reserved_until)
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.opponent.is_some() || self.filled_amount > 0
+    }
+
+    pub fn set_partial_fills(&mut self) {
+        self.partial_fills = true;
+    }
+
+    pub fn set_rating_band(&mut self, host_rating: u16, rating_band: u16) {
+        self.host_rating = host_rating;
+        self.rating_band = rating_band;
+    }
+
+    pub fn accepts_rating(&self, rating: u16) -> bool {
+        self.rating_band == 0 || rating.abs_diff(self.host_rating) <= self.rating_band
+    }
+
+    /// Opponent stake still open to fills.
+    pub fn unfilled_amount(&self) -> u64 {
+        self.opponent_amount.saturating_sub(self.filled_amount)
     }
 
-    pub fn check_price_fluctuation(
-        &mut self,
-        prices: &Vec<u64>,
-        max_percentage: u16,
-        price_decimals: u8,
+    pub fn fill(&mut self, amount: u64) -> Option<()> {
+        self.filled_amount = self.filled_amount.checked_add(amount)?;
+        Some(())
+    }
+
+    /// Part of the host stake covered by fills, in proportion to the filled opponent stake.
+    pub fn matched_amount(&self) -> Option<u64> {
+        if !self.partial_fills {
+            return Some(self.amount);
+        }
+        if self.opponent_amount == 0 {
+            return Some(0);
+        }
+
+        let matched =
+            ((self.amount as u128) * (self.filled_amount as u128)) / (self.opponent_amount as u128);
+        u64::try_from(matched).ok()
+    }
+
+    /// Stops further fills and returns the unmatched host stake to refund.
+    pub fn freeze_fills(&mut self) -> Option<u64> {
+        let matched = self.matched_amount()?;
+        let remainder = self.amount.checked_sub(matched)?;
+
+        self.amount = matched;
+        self.opponent_amount = self.filled_amount;
+
+        Some(remainder)
+    }
+
+    /// Payout of a winning fill: the fill itself plus its share of the matched host stake.
+    pub fn fill_payout(&self, fill_amount: u64) -> Option<u64> {
+        if self.filled_amount == 0 {
+            return None;
+        }
+
+        let share =
+            ((self.matched_amount()? as u128) * (fill_amount as u128)) /
+            (self.filled_amount as u128);
+        fill_amount.checked_add(u64::try_from(share).ok()?)
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    /// Whether an opponent joining at `now` still has `MIN_JOIN_DURATION` seconds of play left.
+    pub fn is_joinable(&self, now: i64) -> bool {
+        match self.expires_at {
+            Some(expires_at) => now.saturating_add(MIN_JOIN_DURATION) <= expires_at,
+            None => true,
+        }
+    }
+
+    /// Checks the price accounts passed by the caller belong to this game's markets.
+    pub fn validate_feeds(&self, prices: &Prices, opponent_prices: Option<&Prices>) -> bool {
+        prices.market == self.market &&
+            opponent_prices.map(|prices| prices.market) == self.opponent_market
+    }
+
+    /// Whether the price stayed within the join threshold since the game was created.
+    pub fn check_join_threshold(
+        &self,
+        prices: &Prices,
+        opponent_prices: Option<&Prices>,
         percentage_decimals: u8
+    ) -> bool {
+        let join_percentage = self.thresholds.join_threshold_percent;
+
+        match self.mode {
+            GameMode::Directional | GameMode::Range | GameMode::Buckets =>
+                check_price_fluctuation(
+                    &prices.prices,
+                    self.price_index as usize,
+                    join_percentage,
+                    prices.decimals,
+                    percentage_decimals
+                ).is_none(),
+            GameMode::Relative =>
+                opponent_prices.is_some_and(|opponent_prices| {
+                    check_relative_performance(
+                        &prices.series(self.price_index),
+                        &opponent_prices.series(self.opponent_price_index.unwrap_or_default()),
+                        join_percentage,
+                        join_percentage,
+                        percentage_decimals,
+                        None,
+                        0
+                    ).is_none()
+                }),
+        }
+    }
+
+    /// Relative game tied at expiry, refunded to both sides like a cancelled game.
+    pub fn is_draw(
+        &self,
+        prices: &Prices,
+        opponent_prices: Option<&Prices>,
+        percentage_decimals: u8,
+        now: i64
+    ) -> bool {
+        self.mode == GameMode::Relative &&
+            self.is_expired(now) &&
+            self.check_result(prices, opponent_prices, percentage_decimals, now).is_none()
+    }
+
+    /// Resolves the game outcome in the terms of `host_prediction`, or `None` while undecided and
+    /// for draws.
+    pub fn check_result(
+        &self,
+        prices: &Prices,
+        opponent_prices: Option<&Prices>,
+        percentage_decimals: u8,
+        now: i64
     ) -> Option<bool> {
-        check_price_fluctuation(
-            prices,
-            self.price_index as usize,
-            max_percentage,
-            price_decimals,
-            percentage_decimals
-        )
+        let ThresholdTier { up_threshold_percent, down_threshold_percent, .. } = self.thresholds;
+
+        match self.mode {
+            GameMode::Directional =>
+                check_price_thresholds(
+                    &prices.prices,
+                    self.price_index as usize,
+                    up_threshold_percent,
+                    down_threshold_percent,
+                    prices.decimals,
+                    percentage_decimals
+                ),
+            GameMode::Range =>
+                check_price_range(
+                    prices.prices_before(self.expires_at?),
+                    self.price_index as usize,
+                    up_threshold_percent,
+                    down_threshold_percent,
+                    prices.decimals,
+                    percentage_decimals,
+                    self.is_expired(now)
+                ),
+            GameMode::Relative =>
+                check_relative_performance(
+                    &prices.series(self.price_index),
+                    &opponent_prices?.series(self.opponent_price_index?),
+                    up_threshold_percent,
+                    down_threshold_percent,
+                    percentage_decimals,
+                    self.expires_at,
+                    now
+                ),
+            GameMode::Buckets =>
+                check_price_buckets(
+                    &prices.prices,
+                    self.price_index as usize,
+                    up_threshold_percent,
+                    down_threshold_percent,
+                    prices.decimals,
+                    percentage_decimals
+                ).map(|outcome| Some(outcome) == self.outcome),
+        }
     }
 }
@@ -1,6 +1,43 @@
 #[cfg(test)]
 mod tests {
-    use crate::utils::check_price_fluctuation;
+    use anchor_lang::prelude::Pubkey;
+
+    use crate::constants::{
+        MAX_LEADERBOARD_SIZE,
+        MAX_OPEN_GAMES,
+        MIN_JOIN_DURATION,
+        REMATCH_WINDOW,
+    };
+
+    use crate::state::{
+        Balance,
+        Game,
+        GameMode,
+        Games,
+        Intent,
+        IntentQueue,
+        OpenGames,
+        Outcome,
+        PlayerProfile,
+        Prices,
+        Season,
+        SeasonScore,
+        Session,
+        SidePool,
+        ThresholdTier,
+        VaultReport,
+        WagerMint,
+    };
+    use crate::utils::{
+        check_price_buckets,
+        check_price_fluctuation,
+        check_price_range,
+        check_price_thresholds,
+        check_relative_performance,
+        elo_ratings,
+        split_stakes,
+        PriceSeries,
+    };
 
     #[test]
     fn test_price_up_first() {
@@ -365,4 +402,774 @@ mod tests {
             Some(false) // First fluctuation meeting the threshold is a 5% decrease
         );
     }
+
+    #[test]
+    fn test_range_breakout_up() {
+        let prices = vec![100_000, 102_000, 105_000];
+        assert_eq!(check_price_range(&prices, 0, 5, 5, 3, 0, false), Some(true));
+    }
+
+    #[test]
+    fn test_range_breakout_down_after_expiry() {
+        let prices = vec![100_000, 98_000, 95_000];
+        assert_eq!(check_price_range(&prices, 0, 5, 5, 3, 0, true), Some(true));
+    }
+
+    #[test]
+    fn test_range_in_band_before_expiry() {
+        let prices = vec![100_000, 104_999, 95_001];
+        assert_eq!(check_price_range(&prices, 0, 5, 5, 3, 0, false), None);
+    }
+
+    #[test]
+    fn test_range_in_band_at_expiry() {
+        let prices = vec![100_000, 104_999, 95_001];
+        assert_eq!(check_price_range(&prices, 0, 5, 5, 3, 0, true), Some(false));
+    }
+
+    #[test]
+    fn test_range_single_price_at_expiry() {
+        let prices = vec![100_000];
+        assert_eq!(check_price_range(&prices, 0, 5, 5, 3, 0, true), Some(false));
+    }
+
+    #[test]
+    fn test_buckets_first_crossing_small() {
+        // The first price out of the band settles the game, the later +12% does not count
+        let prices = vec![100_000, 103_000, 106_000, 112_000];
+        assert_eq!(check_price_buckets(&prices, 0, 5, 5, 3, 0), Some(Outcome::UpSmall));
+
+        let prices = vec![100_000, 97_000, 94_000, 80_000];
+        assert_eq!(check_price_buckets(&prices, 0, 5, 5, 3, 0), Some(Outcome::DownSmall));
+    }
+
+    #[test]
+    fn test_buckets_first_crossing_big() {
+        // A price gapping past twice the threshold lands in the big bucket
+        let prices = vec![100_000, 102_000, 110_000, 95_000];
+        assert_eq!(check_price_buckets(&prices, 0, 5, 5, 3, 0), Some(Outcome::UpBig));
+
+        let prices = vec![100_000, 88_000, 105_000];
+        assert_eq!(check_price_buckets(&prices, 0, 5, 5, 3, 0), Some(Outcome::DownBig));
+    }
+
+    #[test]
+    fn test_buckets_asymmetric_boundaries() {
+        // UP at +5% and +10%, DOWN at -2% and -4%
+        let prices = vec![100_000, 103_000, 96_000];
+        assert_eq!(check_price_buckets(&prices, 0, 5, 2, 3, 0), Some(Outcome::DownBig));
+
+        let prices = vec![100_000, 99_000, 109_000];
+        assert_eq!(check_price_buckets(&prices, 0, 5, 2, 3, 0), Some(Outcome::UpSmall));
+    }
+
+    #[test]
+    fn test_buckets_inside_band() {
+        let prices = vec![100_000, 104_999, 95_001];
+        assert_eq!(check_price_buckets(&prices, 0, 5, 5, 3, 0), None);
+        assert_eq!(check_price_buckets(&prices, 3, 5, 5, 3, 0), None);
+    }
+
+    #[test]
+    fn test_bucket_game() {
+        let mut game = Game::new(
+            Pubkey::default(),
+            false,
+            Pubkey::default(),
+            0,
+            0,
+            GameMode::Buckets,
+            None
+        );
+        game.set_tier(0, ThresholdTier {
+            up_threshold_percent: 5,
+            down_threshold_percent: 5,
+            join_threshold_percent: 1,
+        });
+        assert_eq!(game.stakes(100), None);
+
+        game.set_outcome(Outcome::UpBig);
+        assert!(game.host_prediction);
+        assert_eq!(game.stakes(100), Some((100, 400)));
+
+        let mut prices = Prices {
+            prices: vec![100_000, 103_000],
+            decimals: 3,
+            timestamps: vec![0, 1],
+            market: 0,
+        };
+        assert_eq!(game.check_result(&prices, None, 0, 1), None);
+
+        // The host backed the big bucket, the small one comes first
+        prices.prices.push(106_000);
+        assert_eq!(game.check_result(&prices, None, 0, 2), Some(false));
+
+        game.set_outcome(Outcome::UpSmall);
+        assert_eq!(game.stakes(100), Some((100, 100)));
+        assert_eq!(game.check_result(&prices, None, 0, 2), Some(true));
+    }
+
+    fn series<'a>(prices: &'a [u64], timestamps: &'a [i64]) -> PriceSeries<'a> {
+        PriceSeries { prices, timestamps, start_index: 0 }
+    }
+
+    #[test]
+    fn test_relative_a_outperforms() {
+        let a = series(&[100_000, 103_000], &[0, 10]);
+        let b = series(&[2_000, 1_960], &[0, 20]);
+        // 3% - 0% at t=10, 3% - (-2%) = 5% at t=20
+        assert_eq!(check_relative_performance(&a, &b, 5, 5, 0, None, 0), Some(true));
+    }
+
+    #[test]
+    fn test_relative_b_outperforms() {
+        let a = series(&[100_000, 98_000], &[0, 10]);
+        let b = series(&[2_000, 2_060], &[0, 10]);
+        assert_eq!(check_relative_performance(&a, &b, 5, 5, 0, None, 0), Some(false));
+    }
+
+    #[test]
+    fn test_relative_aligned_on_latest_price() {
+        // b jumps 6% at t=5, but a only catches up at t=15, so b leads by the threshold first
+        let a = series(&[100_000, 106_000], &[0, 15]);
+        let b = series(&[2_000, 2_120], &[0, 5]);
+        assert_eq!(check_relative_performance(&a, &b, 5, 5, 0, None, 0), Some(false));
+    }
+
+    #[test]
+    fn test_relative_undecided_without_expiry() {
+        let a = series(&[100_000, 102_000], &[0, 10]);
+        let b = series(&[2_000, 1_980], &[0, 10]);
+        assert_eq!(check_relative_performance(&a, &b, 5, 5, 0, None, 100), None);
+    }
+
+    #[test]
+    fn test_relative_better_performer_at_expiry() {
+        let a = series(&[100_000, 102_000, 80_000], &[0, 10, 30]);
+        let b = series(&[2_000, 1_980], &[0, 10]);
+        assert_eq!(check_relative_performance(&a, &b, 5, 5, 0, Some(20), 15), None);
+        // the crash at t=30 happens after expiry and is ignored
+        assert_eq!(check_relative_performance(&a, &b, 5, 5, 0, Some(20), 40), Some(true));
+    }
+
+    #[test]
+    fn test_relative_tie_at_expiry() {
+        // the drop at t=30 happens after expiry and does not break the tie, neither side wins
+        let a = series(&[100_000, 100_000, 99_000], &[0, 10, 30]);
+        let b = series(&[2_000, 2_000], &[0, 10]);
+        assert_eq!(check_relative_performance(&a, &b, 5, 5, 0, Some(20), 25), None);
+
+        // A tie at expiry is a draw, refunded to both sides
+        let mut game = Game::new(
+            Pubkey::new_unique(),
+            true,
+            Pubkey::default(),
+            0,
+            0,
+            GameMode::Relative,
+            Some(20)
+        );
+        game.set_opponent_feed(1, 0);
+        game.set_tier(0, ThresholdTier {
+            up_threshold_percent: 5,
+            down_threshold_percent: 5,
+            join_threshold_percent: 1,
+        });
+        let prices = Prices {
+            prices: vec![100_000, 100_000],
+            decimals: 3,
+            timestamps: vec![0, 10],
+            market: 0,
+        };
+        let mut opponent_prices = Prices {
+            prices: vec![2_000, 2_000],
+            decimals: 3,
+            timestamps: vec![0, 10],
+            market: 1,
+        };
+        assert!(!game.is_draw(&prices, Some(&opponent_prices), 0, 15));
+        assert!(game.is_draw(&prices, Some(&opponent_prices), 0, 25));
+        assert_eq!(game.check_result(&prices, Some(&opponent_prices), 0, 25), None);
+
+        // Any spread at expiry decides the game instead
+        opponent_prices.prices[1] = 1_990;
+        assert!(!game.is_draw(&prices, Some(&opponent_prices), 0, 25));
+        assert_eq!(game.check_result(&prices, Some(&opponent_prices), 0, 25), Some(true));
+    }
+
+    #[test]
+    fn test_relative_invalid_start_index() {
+        let a = PriceSeries { prices: &[100_000], timestamps: &[0], start_index: 1 };
+        let b = series(&[2_000], &[0]);
+        assert_eq!(check_relative_performance(&a, &b, 5, 5, 0, None, 0), None);
+    }
+
+    #[test]
+    fn test_asymmetric_thresholds_up_first() {
+        let prices = vec![
+            100_000, // 100.000
+            96_000, // 96.000 (4% decrease, inside the 5% down threshold)
+            110_000 // 110.000 (10% increase)
+        ];
+        assert_eq!(check_price_thresholds(&prices, 0, 10, 5, 3, 0), Some(true));
+    }
+
+    #[test]
+    fn test_asymmetric_thresholds_down_first() {
+        let prices = vec![
+            100_000, // 100.000
+            109_000, // 109.000 (9% increase, inside the 10% up threshold)
+            95_000 // 95.000 (5% decrease)
+        ];
+        assert_eq!(check_price_thresholds(&prices, 0, 10, 5, 3, 0), Some(false));
+    }
+
+    #[test]
+    fn test_asymmetric_range_band() {
+        let prices = vec![100_000, 109_000, 96_000];
+        assert_eq!(check_price_range(&prices, 0, 10, 5, 3, 0, true), Some(false));
+        assert_eq!(check_price_range(&prices, 0, 10, 3, 3, 0, true), Some(true));
+    }
+
+    #[test]
+    fn test_asymmetric_relative_spread() {
+        let a = series(&[100_000, 96_000], &[0, 10]);
+        let b = series(&[2_000, 2_000], &[0, 10]);
+        assert_eq!(check_relative_performance(&a, &b, 10, 5, 0, None, 0), None);
+        assert_eq!(check_relative_performance(&a, &b, 10, 4, 0, None, 0), Some(false));
+    }
+
+    #[test]
+    fn test_split_stakes_symmetric() {
+        assert_eq!(split_stakes(1_000, 500, 500), Some((1_000, 1_000)));
+    }
+
+    #[test]
+    fn test_split_stakes_asymmetric() {
+        // +10% / -5%: UP is hit first a third of the time
+        assert_eq!(split_stakes(1_500, 1000, 500), Some((1_000, 2_000)));
+        assert_eq!(split_stakes(1_500, 500, 1000), Some((2_000, 1_000)));
+    }
+
+    #[test]
+    fn test_split_stakes_keeps_pot() {
+        let (up_stake, down_stake) = split_stakes(1_000, 700, 300).unwrap();
+        assert_eq!(up_stake + down_stake, 2_000);
+        assert_eq!(up_stake, 600);
+    }
+
+    #[test]
+    fn test_split_stakes_invalid() {
+        assert_eq!(split_stakes(1_000, 0, 0), None);
+        assert_eq!(split_stakes(u64::MAX, 1, 1), Some((u64::MAX, u64::MAX)));
+    }
+
+    #[test]
+    fn test_wager_mint_limits() {
+        let wager_mint = WagerMint {
+            mint: Pubkey::default(),
+            min_bet_size: 100,
+            max_bet_size: 1_000,
+            is_enabled: true,
+            locked_amount: 0,
+            fees: 0,
+            season: None,
+        };
+        assert!(wager_mint.accepts(100));
+        assert!(wager_mint.accepts(1_000));
+        assert!(!wager_mint.accepts(99));
+        assert!(!wager_mint.accepts(1_001));
+    }
+
+    #[test]
+    fn test_vault_report() {
+        let mut wager_mint = WagerMint {
+            mint: Pubkey::default(),
+            min_bet_size: 100,
+            max_bet_size: 1_000,
+            is_enabled: true,
+            locked_amount: 0,
+            fees: 0,
+            season: None,
+        };
+        wager_mint.lock(1_000).unwrap();
+        wager_mint.lock(500).unwrap();
+        wager_mint.release(1_000).unwrap();
+        assert_eq!(wager_mint.locked_amount, 500);
+        assert_eq!(wager_mint.release(501), None);
+
+        assert!(wager_mint.is_covered_by(500));
+        assert!(!wager_mint.is_covered_by(499));
+
+        let report = VaultReport::new(&wager_mint, 700);
+        assert_eq!((report.surplus, report.deficit), (200, 0));
+
+        let report = VaultReport::new(&wager_mint, 300);
+        assert_eq!((report.surplus, report.deficit), (0, 200));
+    }
+
+    #[test]
+    fn test_balance_debit() {
+        let mut balance = Balance { player: Pubkey::default(), mint: Pubkey::default(), amount: 0 };
+        balance.credit(1_000).unwrap();
+        balance.debit(400).unwrap();
+        assert_eq!(balance.amount, 600);
+        assert_eq!(balance.debit(601), None);
+        assert_eq!(balance.amount, 600);
+    }
+
+    fn intent(id: u64, player: u8, prediction: bool, bet_size: u64) -> Intent {
+        Intent {
+            id,
+            player: Pubkey::new_from_array([player; 32]),
+            prediction,
+            mint: Pubkey::default(),
+            bet_size,
+            tier: 0,
+            thresholds: ThresholdTier {
+                up_threshold_percent: 5,
+                down_threshold_percent: 5,
+                join_threshold_percent: 1,
+            },
+            amount: bet_size,
+            price_index: 0,
+            spam_deposit: 0,
+        }
+    }
+
+    #[test]
+    fn test_intent_matching_is_fifo() {
+        let mut queue = IntentQueue { market: 0, next_id: 0, intents: vec![] };
+        assert_eq!(queue.next_match(&[]), None);
+
+        queue.intents = vec![
+            intent(0, 1, true, 100),
+            intent(1, 1, false, 100), // same player
+            intent(2, 2, true, 100), // same side
+            intent(3, 3, false, 200), // other bet size
+            intent(4, 4, false, 100),
+            intent(5, 5, false, 100)
+        ];
+        assert_eq!(queue.next_match(&[true; 6]), Some((0, 4)));
+        assert_eq!(queue.intents_of(Pubkey::new_from_array([1; 32])), 2);
+
+        // Intents the price moved away from are skipped on both sides
+        assert_eq!(queue.next_match(&[true, true, true, true, false, true]), Some((0, 5)));
+        assert_eq!(queue.next_match(&[false, true, true, true, true, true]), Some((1, 2)));
+
+        queue.intents.remove(4);
+        queue.intents.remove(0);
+        // Intent 1 now matches the oldest UP intent of another player
+        assert_eq!(queue.next_match(&[true; 4]), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_open_games_prune() {
+        let game = || Game::new(
+            Pubkey::default(),
+            true,
+            Pubkey::default(),
+            0,
+            0,
+            GameMode::Directional,
+            None
+        );
+        let mut joined = game();
+        joined.join(Pubkey::new_unique(), 100);
+        let mut closed = game();
+        closed.close();
+        let games = vec![game(), joined, closed, game()];
+
+        let mut open_games = OpenGames { market: 0, game_indices: vec![0, 1, 2, 3, 4] };
+        open_games.prune(&games);
+        assert_eq!(open_games.game_indices, vec![0, 3]);
+
+        // A full index skips new games instead of failing
+        let open = vec![game(); MAX_OPEN_GAMES + 1];
+        let mut full = OpenGames {
+            market: 0,
+            game_indices: (0..MAX_OPEN_GAMES as u32).collect(),
+        };
+        full.insert(&open, 0, MAX_OPEN_GAMES as u32);
+        assert_eq!(full.game_indices.len(), MAX_OPEN_GAMES);
+        full.insert(&games, 0, 4);
+        assert_eq!(full.game_indices, vec![0, 3, 4]);
+
+        let games = Games { games };
+        assert_eq!(games.open_games_of(Pubkey::default()), 2);
+        assert_eq!(games.open_games_of(Pubkey::new_unique()), 0);
+    }
+
+    #[test]
+    fn test_partial_fills() {
+        let mut game = Game::new(
+            Pubkey::default(),
+            true,
+            Pubkey::default(),
+            0,
+            0,
+            GameMode::Directional,
+            None
+        );
+        game.set_stakes(1_000, 1_000);
+        game.set_partial_fills();
+        assert!(!game.is_started());
+
+        game.fill(300).unwrap();
+        game.fill(100).unwrap();
+        assert!(game.is_started());
+        assert_eq!(game.unfilled_amount(), 600);
+        assert_eq!(game.matched_amount(), Some(400));
+
+        // Fillers share the matched host stake pro rata
+        assert_eq!(game.fill_payout(300), Some(600));
+        assert_eq!(game.fill_payout(100), Some(200));
+
+        assert_eq!(game.freeze_fills(), Some(600));
+        assert_eq!((game.amount, game.opponent_amount), (400, 400));
+        assert_eq!(game.unfilled_amount(), 0);
+        assert_eq!(game.freeze_fills(), Some(0));
+        assert_eq!(game.fill_payout(300), Some(600));
+    }
+
+    #[test]
+    fn test_partial_fills_with_asymmetric_stakes() {
+        let mut game = Game::new(
+            Pubkey::default(),
+            true,
+            Pubkey::default(),
+            0,
+            0,
+            GameMode::Directional,
+            None
+        );
+        game.set_stakes(1_000, 2_000);
+        game.set_partial_fills();
+        game.fill(500).unwrap();
+
+        assert_eq!(game.matched_amount(), Some(250));
+        assert_eq!(game.fill_payout(500), Some(750));
+    }
+
+    #[test]
+    fn test_player_profile_streaks() {
+        let mut profile = PlayerProfile {
+            player: Pubkey::default(),
+            referrer: None,
+            games_played: 0,
+            wins: 0,
+            losses: 0,
+            withdrawals: 0,
+            total_wagered: 0,
+            total_won: 0,
+            current_streak: 0,
+            best_streak: 0,
+            rating: 0,
+        };
+        for _ in 0..4 {
+            profile.record_wager(100).unwrap();
+        }
+        profile.record_win(200).unwrap();
+        profile.record_win(200).unwrap();
+        profile.record_loss().unwrap();
+        profile.record_win(200).unwrap();
+        assert_eq!((profile.wins, profile.losses, profile.total_won), (3, 1, 600));
+        assert_eq!((profile.current_streak, profile.best_streak), (1, 2));
+
+        profile.record_wager(100).unwrap();
+        profile.record_withdrawal(100, true).unwrap();
+        assert_eq!((profile.games_played, profile.withdrawals, profile.total_wagered), (4, 1, 400));
+    }
+
+    #[test]
+    fn test_wager_mint_fee() {
+        let mut wager_mint = WagerMint {
+            mint: Pubkey::default(),
+            min_bet_size: 1,
+            max_bet_size: 1_000,
+            is_enabled: true,
+            locked_amount: 2_000,
+            fees: 0,
+            season: None,
+        };
+        assert_eq!(wager_mint.take_fee(2_000, 250), Some(1_950));
+        assert_eq!(wager_mint.take_fee(2_000, 0), Some(2_000));
+        assert_eq!(wager_mint.fees, 50);
+
+        // Referrers get their share out of the accrued fees
+        assert_eq!(wager_mint.share_fee(50, 2_000), Some(10));
+        assert_eq!(wager_mint.fees, 40);
+        assert_eq!(wager_mint.share_fee(50, 10_000), None);
+    }
+
+    #[test]
+    fn test_season_leaderboard() {
+        let mut season = Season {
+            id: 0,
+            mint: Pubkey::default(),
+            start_time: 100,
+            end_time: 200,
+            prize_pool: 0,
+            is_closed: false,
+            leaderboard: vec![],
+        };
+        assert!(!season.is_active(99));
+        assert!(season.is_active(100));
+        assert!(!season.is_active(200));
+
+        season.record(Pubkey::new_from_array([99; 32]), 0);
+        assert!(season.leaderboard.is_empty());
+
+        for player in 1..=(MAX_LEADERBOARD_SIZE as u8) {
+            season.record(Pubkey::new_from_array([player; 32]), (player as u64) * 10);
+        }
+        assert_eq!(season.leaderboard[0].net_winnings, 100);
+        assert_eq!(season.leaderboard.last().unwrap().net_winnings, 10);
+
+        // Lower scores don't enter a full leaderboard, higher ones evict the last entry
+        season.record(Pubkey::new_from_array([42; 32]), 5);
+        assert_eq!(season.leaderboard.len(), MAX_LEADERBOARD_SIZE);
+        assert!(season.leaderboard.iter().all(|entry| entry.net_winnings != 5));
+        season.record(Pubkey::new_from_array([42; 32]), 15);
+        assert_eq!(season.leaderboard.last().unwrap().net_winnings, 15);
+
+        // Existing entries move up with their new total
+        season.record(Pubkey::new_from_array([42; 32]), 500);
+        assert_eq!(season.leaderboard[0].player, Pubkey::new_from_array([42; 32]));
+
+        // Losses move ranked players down but don't add new ones
+        season.record(Pubkey::new_from_array([42; 32]), 0);
+        assert_eq!(season.leaderboard.last().unwrap().player, Pubkey::new_from_array([42; 32]));
+    }
+
+    #[test]
+    fn test_season_distribution() {
+        let mut season = Season {
+            id: 0,
+            mint: Pubkey::default(),
+            start_time: 0,
+            end_time: 1,
+            prize_pool: 0,
+            is_closed: false,
+            leaderboard: vec![],
+        };
+        assert_eq!(season.distribute(1_000), Some(0));
+
+        season.record(Pubkey::new_from_array([1; 32]), 200);
+        season.record(Pubkey::new_from_array([2; 32]), 100);
+        assert_eq!(season.distribute(1_000), Some(999));
+        assert_eq!(season.leaderboard[0].prize, 666);
+        assert_eq!(season.leaderboard[1].prize, 333);
+        assert_eq!(season.prize_pool, 999);
+    }
+
+    #[test]
+    fn test_season_score() {
+        let mut score = SeasonScore { season: 0, player: Pubkey::default(), net_winnings: 0 };
+        score.record_win(300).unwrap();
+        score.record_loss(100);
+        assert_eq!(score.net_winnings, 200);
+
+        // Losses beyond the winnings floor the score at zero
+        score.record_loss(500);
+        assert_eq!(score.net_winnings, 0);
+        assert_eq!(score.record_win(u64::MAX), Some(()));
+        assert_eq!(score.record_win(1), None);
+
+        // Scores created for a loser are set up once
+        let player = Pubkey::new_unique();
+        score.init(3, player);
+        score.init(4, Pubkey::new_unique());
+        assert_eq!((score.season, score.player), (3, player));
+    }
+
+    #[test]
+    fn test_elo_ratings() {
+        assert_eq!(elo_ratings(1_200, 1_200), (1_216, 1_184));
+        // Upsets move ratings further than expected wins
+        assert_eq!(elo_ratings(1_000, 1_400), (1_029, 1_371));
+        assert_eq!(elo_ratings(1_400, 1_000), (1_403, 997));
+        assert_eq!(elo_ratings(1_200, 10), (1_200, 10));
+        // Steps of the table are where the rounded change drops by a point
+        assert_eq!(elo_ratings(1_210, 1_200), (1_226, 1_184));
+        assert_eq!(elo_ratings(1_211, 1_200), (1_226, 1_185));
+        assert_eq!(elo_ratings(480, 1_200), (512, 1_168));
+        assert_eq!(elo_ratings(481, 1_200), (512, 1_169));
+        assert_eq!(elo_ratings(1_920, 1_200), (1_920, 1_200));
+    }
+
+    #[test]
+    fn test_rating_band() {
+        let mut game = Game::new(
+            Pubkey::default(),
+            true,
+            Pubkey::default(),
+            0,
+            0,
+            GameMode::Directional,
+            None
+        );
+        game.set_rating_band(1_200, 0);
+        assert!(game.accepts_rating(2_400));
+
+        game.set_rating_band(1_200, 100);
+        assert!(game.accepts_rating(1_100));
+        assert!(game.accepts_rating(1_300));
+        assert!(!game.accepts_rating(1_301));
+        assert!(!game.accepts_rating(1_099));
+    }
+
+    #[test]
+    fn test_session() {
+        let player = Pubkey::new_unique();
+        let session_key = Pubkey::new_unique();
+        let mut session = Session {
+            player,
+            session_key,
+            expires_at: 100,
+            spending_cap: 50,
+            spent: 0,
+        };
+
+        assert!(session.authorizes(player, session_key, 99));
+        assert!(!session.authorizes(player, session_key, 100));
+        assert!(!session.authorizes(session_key, player, 99));
+
+        assert_eq!(session.spend(30), Some(()));
+        assert_eq!(session.spend(30), None);
+        assert_eq!(session.spend(20), Some(()));
+        assert_eq!(session.spent, 50);
+    }
+
+    #[test]
+    fn test_cancel_game() {
+        let host = Pubkey::new_unique();
+        let opponent = Pubkey::new_unique();
+        let mut game = Game::new(host, true, Pubkey::default(), 0, 0, GameMode::Directional, None);
+        assert_eq!(game.counterparty(host), None);
+
+        game.join(opponent, 100);
+        assert_eq!(game.counterparty(host), Some(opponent));
+        assert_eq!(game.counterparty(opponent), Some(host));
+        assert_eq!(game.counterparty(Pubkey::new_unique()), None);
+
+        // Proposing twice does not agree with oneself
+        assert!(!game.propose_cancel(host));
+        assert!(!game.propose_cancel(host));
+
+        // Only the proposer can withdraw, after which the other side's proposal is a new one
+        assert!(!game.withdraw_cancel(opponent));
+        assert!(game.withdraw_cancel(host));
+        assert_eq!(game.cancel_proposer, None);
+        assert!(!game.propose_cancel(opponent));
+        assert!(game.propose_cancel(host));
+
+        game.cancel();
+        assert!(game.is_cancelled);
+        assert!(game.is_closed);
+    }
+
+    #[test]
+    fn test_rematch() {
+        let host = Pubkey::new_unique();
+        let opponent = Pubkey::new_unique();
+        let mut game = Game::new(host, true, Pubkey::default(), 0, 0, GameMode::Directional, None);
+        game.join(opponent, 50);
+        game.set_stakes(100, 50);
+        assert!(game.rematch(Pubkey::new_unique(), false, 9, None, 0).is_none());
+
+        // The opponent hosts the rematch on their side, keeping their stake
+        let rematch = game.rematch(opponent, false, 9, None, 1_000).unwrap();
+        assert_eq!(rematch.host, opponent);
+        assert!(!rematch.host_prediction);
+        assert_eq!((rematch.amount, rematch.opponent_amount), (50, 100));
+        assert_eq!(rematch.price_index, 9);
+        assert_eq!(rematch.reserved_for, Some(host));
+        assert_eq!(rematch.reserved_until, 1_000 + REMATCH_WINDOW);
+
+        let swapped = game.rematch(opponent, true, 9, None, 1_000).unwrap();
+        assert!(swapped.host_prediction);
+        assert_eq!((swapped.amount, swapped.opponent_amount), (100, 50));
+
+        game.is_rematched = true;
+        assert!(game.rematch(opponent, false, 9, None, 1_000).is_none());
+
+        assert!(rematch.accepts_opponent(host, 1_000));
+        assert!(!rematch.accepts_opponent(Pubkey::new_unique(), 1_000));
+        assert!(rematch.accepts_opponent(Pubkey::new_unique(), 1_000 + REMATCH_WINDOW));
+    }
+
+    #[test]
+    fn test_rematch_keeps_mode_terms() {
+        let host = Pubkey::new_unique();
+        let opponent = Pubkey::new_unique();
+        let mut game = Game::new(host, true, Pubkey::default(), 0, 0, GameMode::Relative, None);
+        game.duration = Some(400);
+        game.set_opponent_feed(1, 3);
+        game.join(opponent, 100);
+
+        // Relative rematches need the latest index of the opponent feed
+        assert!(game.rematch(opponent, false, 9, None, 1_000).is_none());
+        let rematch = game.rematch(opponent, false, 9, Some(7), 1_000).unwrap();
+        assert_eq!(rematch.mode, GameMode::Relative);
+        assert_eq!((rematch.opponent_market, rematch.opponent_price_index), (Some(1), Some(7)));
+        assert_eq!((rematch.duration, rematch.expires_at), (Some(400), Some(1_400)));
+
+        let mut buckets = Game::new(host, true, Pubkey::default(), 0, 0, GameMode::Buckets, None);
+        buckets.join(opponent, 100);
+        assert!(buckets.rematch(host, false, 9, None, 1_000).is_none());
+    }
+
+    #[test]
+    fn test_side_pool_payout() {
+        let mut pool = SidePool {
+            game_index: 0,
+            mint: Pubkey::default(),
+            host_total: 0,
+            opponent_total: 0,
+            host_won: None,
+        };
+        pool.back(true, 300).unwrap();
+        pool.back(true, 100).unwrap();
+        pool.back(false, 200).unwrap();
+        assert_eq!((pool.host_total, pool.opponent_total), (400, 200));
+
+        // Cancelled games refund every bet
+        assert_eq!(pool.payout(false, 200), Some(200));
+
+        // Winners share the whole pool pro rata
+        pool.host_won = Some(true);
+        assert_eq!(pool.payout(true, 300), Some(450));
+        assert_eq!(pool.payout(true, 100), Some(150));
+        assert_eq!(pool.payout(false, 200), Some(0));
+        assert!(pool.has_winnings(true));
+        assert!(!pool.has_winnings(false));
+
+        // Nobody backed the winner, the losing side gets its stakes back
+        pool.host_total = 0;
+        assert_eq!(pool.payout(false, 200), Some(200));
+        assert!(!pool.has_winnings(false));
+
+        // Nobody backed the loser, the winners only get their stakes back
+        pool.host_total = 400;
+        pool.opponent_total = 0;
+        assert_eq!(pool.payout(true, 300), Some(300));
+        assert!(!pool.has_winnings(true));
+    }
+
+    #[test]
+    fn test_join_window() {
+        let game = Game::new(
+            Pubkey::default(),
+            true,
+            Pubkey::default(),
+            0,
+            0,
+            GameMode::Range,
+            Some(1_000)
+        );
+        assert!(game.is_joinable(1_000 - MIN_JOIN_DURATION));
+        assert!(!game.is_joinable(1_001 - MIN_JOIN_DURATION));
+        assert!(!game.is_joinable(1_000));
+    }
 }
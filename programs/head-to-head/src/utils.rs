@@ -1,7 +1,25 @@
 use anchor_lang::prelude::*;
+use crate::constants::{ BIG_BUCKET_MULTIPLIER, ELO_CHANGE_STEPS, ELO_K_FACTOR, VAULT_SEED };
 use crate::error::Error;
+use crate::state::{
+    AllowlistEntry,
+    Game,
+    MarketAccess,
+    Outcome,
+    PlayerProfile,
+    Session,
+    SolEscrow,
+    WagerMint,
+};
 use anchor_lang::system_program;
-use anchor_spl::token::{ transfer, Transfer };
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_interface::{
+    transfer_checked,
+    Mint,
+    TokenAccount,
+    TokenInterface,
+    TransferChecked,
+};
 use rust_decimal::prelude::*;
 
 pub fn resize_account<'info, T: AccountSerialize + AccountDeserialize + Owner + Clone>(
@@ -37,31 +55,262 @@ pub fn transfer_tokens<'info>(
     from: AccountInfo<'info>,
     to: AccountInfo<'info>,
     authority: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
     amount: u64,
     token_program: AccountInfo<'info>,
     signer_seeds: Option<&[&[&[u8]]]>
 ) -> Result<()> {
-    let cpi_accounts: Transfer = Transfer {
+    let cpi_accounts: TransferChecked = TransferChecked {
         from,
+        mint: mint.to_account_info(),
         to,
         authority,
     };
 
-    let cpi_context: CpiContext<Transfer> = if let Some(seeds) = signer_seeds {
+    let cpi_context: CpiContext<TransferChecked> = if let Some(seeds) = signer_seeds {
         CpiContext::new_with_signer(token_program, cpi_accounts, seeds)
     } else {
         CpiContext::new(token_program, cpi_accounts)
     };
 
-    transfer(cpi_context, amount)
+    transfer_checked(cpi_context, amount, mint.decimals)
+}
+
+/// Transfers `amount` into the vault and returns what the vault actually received, which is
+/// less than `amount` for Token-2022 mints with a transfer fee.
+pub fn deposit_tokens<'info>(
+    from: AccountInfo<'info>,
+    vault: &mut InterfaceAccount<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    amount: u64,
+    token_program: AccountInfo<'info>
+) -> Result<u64> {
+    let balance_before = vault.amount;
+
+    transfer_tokens(from, vault.to_account_info(), authority, mint, amount, token_program, None)?;
+
+    vault.reload()?;
+    let received = vault.amount.checked_sub(balance_before).ok_or(Error::ArithmeticOverflow)?;
+
+    Ok(received)
+}
+
+/// Where the stakes of a mint are held: its token vault, or the SOL escrow for native SOL.
+pub enum Escrow<'a, 'info> {
+    Vault {
+        vault: &'a mut InterfaceAccount<'info, TokenAccount>,
+        player_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+        mint: &'a InterfaceAccount<'info, Mint>,
+        token_program: &'a Interface<'info, TokenInterface>,
+    },
+    Native(&'a Account<'info, SolEscrow>),
+}
+
+impl<'a, 'info> Escrow<'a, 'info> {
+    pub fn new(
+        mint: &'a InterfaceAccount<'info, Mint>,
+        player_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+        vault: Option<&'a mut InterfaceAccount<'info, TokenAccount>>,
+        sol_escrow: Option<&'a Account<'info, SolEscrow>>,
+        token_program: Option<&'a Interface<'info, TokenInterface>>
+    ) -> Result<Self> {
+        if mint.key() == native_mint::ID {
+            let sol_escrow = sol_escrow.ok_or(Error::MissingEscrowAccounts)?;
+            return Ok(Escrow::Native(sol_escrow));
+        }
+
+        match (vault, player_token_account, token_program) {
+            (Some(vault), Some(player_token_account), Some(token_program)) =>
+                Ok(Escrow::Vault { vault, player_token_account, mint, token_program }),
+            _ => err!(Error::MissingEscrowAccounts),
+        }
+    }
+
+    /// Moves `amount` from the player into the escrow and returns the amount received.
+    pub fn deposit(
+        &mut self,
+        player: &Signer<'info>,
+        amount: u64,
+        system_program: &Program<'info, System>
+    ) -> Result<u64> {
+        match self {
+            Escrow::Vault { vault, player_token_account, mint, token_program } =>
+                deposit_tokens(
+                    player_token_account.to_account_info(),
+                    vault,
+                    player.to_account_info(),
+                    mint,
+                    amount,
+                    token_program.to_account_info()
+                ),
+            Escrow::Native(sol_escrow) => {
+                system_program::transfer(
+                    CpiContext::new(system_program.to_account_info(), system_program::Transfer {
+                        from: player.to_account_info(),
+                        to: sol_escrow.to_account_info(),
+                    }),
+                    amount
+                )?;
+                Ok(amount)
+            }
+        }
+    }
+
+    /// Pays `amount` out of the escrow to the player, `vault_bump` signs for token vaults.
+    pub fn pay_out(
+        &mut self,
+        player: &AccountInfo<'info>,
+        amount: u64,
+        vault_bump: Option<u8>
+    ) -> Result<()> {
+        match self {
+            Escrow::Vault { vault, player_token_account, mint, token_program } => {
+                let mint_key = mint.key();
+                let bump = vault_bump.ok_or(Error::MissingEscrowAccounts)?;
+                transfer_tokens(
+                    vault.to_account_info(),
+                    player_token_account.to_account_info(),
+                    vault.to_account_info(),
+                    mint,
+                    amount,
+                    token_program.to_account_info(),
+                    Some(&[&[VAULT_SEED, mint_key.as_ref(), &[bump]]])
+                )
+            }
+            Escrow::Native(sol_escrow) => {
+                sol_escrow.sub_lamports(amount)?;
+                player.add_lamports(amount)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Fails unless the escrow still holds every stake locked for the mint.
+    pub fn check_solvency(&mut self, wager_mint: &WagerMint) -> Result<()> {
+        let balance = match self {
+            Escrow::Vault { vault, .. } => {
+                vault.reload()?;
+                vault.amount
+            }
+            Escrow::Native(sol_escrow) => sol_escrow_balance(sol_escrow)?,
+        };
+
+        require!(wager_mint.is_covered_by(balance), Error::VaultInsolvent);
+        Ok(())
+    }
+}
+
+/// Returns the anti-spam deposit of `game` from the host's profile to the host once the game
+/// is joined or withdrawn.
+pub fn refund_spam_deposit<'info>(
+    game: &mut Game,
+    host: Option<&AccountInfo<'info>>,
+    host_profile: Option<&Account<'info, PlayerProfile>>
+) -> Result<()> {
+    if game.spam_deposit == 0 {
+        return Ok(());
+    }
+
+    match (host, host_profile) {
+        (Some(host), Some(host_profile)) if
+            host.key() == game.host &&
+            host_profile.player == game.host
+        => {
+            host_profile.sub_lamports(game.spam_deposit)?;
+            host.add_lamports(game.spam_deposit)?;
+            game.spam_deposit = 0;
+            Ok(())
+        }
+        _ => err!(Error::MissingHostAccounts),
+    }
+}
+
+/// Reads the access gate of a market from its `MarketAccess` PDA, `None` when the market is
+/// open to everyone and the PDA was never created.
+pub fn load_market_access(market_access: &AccountInfo) -> Result<Option<MarketAccess>> {
+    if market_access.owner != &crate::ID || market_access.data_is_empty() {
+        return Ok(None);
+    }
+    let data = market_access.try_borrow_data()?;
+    Ok(Some(MarketAccess::try_deserialize(&mut &data[..])?))
+}
+
+/// Fails unless `player` holds the gate mint of the market or is on its allowlist.
+pub fn check_market_access(
+    market_access: &MarketAccess,
+    player: Pubkey,
+    access_token_account: Option<&InterfaceAccount<TokenAccount>>,
+    allowlist_entry: Option<&Account<AllowlistEntry>>
+) -> Result<()> {
+    let is_holder = market_access.gate_mint.is_some_and(|gate_mint| {
+        access_token_account.is_some_and(
+            |token_account|
+                token_account.mint == gate_mint &&
+                token_account.owner == player &&
+                token_account.amount > 0
+        )
+    });
+    let is_listed = allowlist_entry.is_some_and(
+        |entry| entry.market == market_access.market && entry.player == player
+    );
+
+    require!(is_holder || is_listed, Error::MarketAccessDenied);
+    Ok(())
+}
+
+/// Checks that `authority` acts for `player`: either the player's own wallet, or a live session
+/// key of the player with `stake` left under its spending cap. Returns whether a session signed.
+pub fn authorize_session(
+    player: &AccountInfo,
+    authority: &Signer,
+    session: Option<&mut Session>,
+    stake: u64,
+    now: i64
+) -> Result<bool> {
+    if authority.key() == player.key() {
+        return Ok(false);
+    }
+
+    let session = session
+        .filter(|session| session.authorizes(player.key(), authority.key(), now))
+        .ok_or(Error::InvalidSession)?;
+    session.spend(stake).ok_or(Error::SessionCapExceeded)?;
+
+    Ok(true)
+}
+
+/// Lamports of the SOL escrow available for wagers, excluding its rent-exempt reserve.
+pub fn sol_escrow_balance(sol_escrow: &Account<SolEscrow>) -> Result<u64> {
+    let reserve = Rent::get()?.minimum_balance(SolEscrow::len());
+    Ok(sol_escrow.get_lamports().saturating_sub(reserve))
 }
 
 pub fn check_price_fluctuation(
-    prices: &Vec<u64>,
+    prices: &[u64],
     start_index: usize,
     max_percentage: u16,
     price_decimals: u8,
     percentage_decimals: u8
+) -> Option<bool> {
+    check_price_thresholds(
+        prices,
+        start_index,
+        max_percentage,
+        max_percentage,
+        price_decimals,
+        percentage_decimals
+    )
+}
+
+/// Like `check_price_fluctuation`, with separate distances to the UP and DOWN thresholds.
+pub fn check_price_thresholds(
+    prices: &[u64],
+    start_index: usize,
+    up_percentage: u16,
+    down_percentage: u16,
+    price_decimals: u8,
+    percentage_decimals: u8
 ) -> Option<bool> {
     if start_index >= prices.len() {
         return None;
@@ -71,11 +320,12 @@ pub fn check_price_fluctuation(
         Decimal::from(*prices.get(start_index)?) /
         Decimal::from((10u32).pow(price_decimals as u32));
 
-    let d_max_percentage =
-        Decimal::from(max_percentage) / Decimal::from((10u32).pow(percentage_decimals as u32));
+    let d_percentage_unit = Decimal::from((10u32).pow(percentage_decimals as u32));
+    let d_up_percentage = Decimal::from(up_percentage) / d_percentage_unit;
+    let d_down_percentage = Decimal::from(down_percentage) / d_percentage_unit;
 
-    let up_threshold = d_start_price * (Decimal::ONE + d_max_percentage / Decimal::from(100));
-    let down_threshold = d_start_price * (Decimal::ONE - d_max_percentage / Decimal::from(100));
+    let up_threshold = d_start_price * (Decimal::ONE + d_up_percentage / Decimal::from(100));
+    let down_threshold = d_start_price * (Decimal::ONE - d_down_percentage / Decimal::from(100));
 
     for &price in &prices[start_index + 1..] {
         let d_price = Decimal::from(price).checked_div(
@@ -91,3 +341,179 @@ pub fn check_price_fluctuation(
 
     None
 }
+
+/// Bucket games: the bucket the first price out of the band between the UP and DOWN thresholds
+/// lands in. That price settles the game even when a later one goes further, it only reaches a
+/// big bucket by crossing `BIG_BUCKET_MULTIPLIER` times the threshold at once.
+pub fn check_price_buckets(
+    prices: &[u64],
+    start_index: usize,
+    up_percentage: u16,
+    down_percentage: u16,
+    price_decimals: u8,
+    percentage_decimals: u8
+) -> Option<Outcome> {
+    let d_price_unit = Decimal::from((10u32).pow(price_decimals as u32));
+    let d_start_price = Decimal::from(*prices.get(start_index)?) / d_price_unit;
+
+    let d_percentage_unit =
+        Decimal::from((10u32).pow(percentage_decimals as u32)) * Decimal::from(100);
+    let d_up_percentage = Decimal::from(up_percentage) / d_percentage_unit;
+    let d_down_percentage = Decimal::from(down_percentage) / d_percentage_unit;
+    let d_multiplier = Decimal::from(BIG_BUCKET_MULTIPLIER);
+
+    for &price in &prices[start_index + 1..] {
+        let d_price = Decimal::from(price) / d_price_unit;
+        let change = (d_price - d_start_price).checked_div(d_start_price)?;
+
+        if change >= d_up_percentage * d_multiplier {
+            return Some(Outcome::UpBig);
+        } else if change >= d_up_percentage {
+            return Some(Outcome::UpSmall);
+        } else if change <= -d_down_percentage * d_multiplier {
+            return Some(Outcome::DownBig);
+        } else if change <= -d_down_percentage {
+            return Some(Outcome::DownSmall);
+        }
+    }
+
+    None
+}
+
+/// Range games: `Some(true)` once the price leaves the band, `Some(false)` if it stayed inside
+/// until expiry. `prices` must only contain prices recorded before expiry.
+pub fn check_price_range(
+    prices: &[u64],
+    start_index: usize,
+    up_percentage: u16,
+    down_percentage: u16,
+    price_decimals: u8,
+    percentage_decimals: u8,
+    is_expired: bool
+) -> Option<bool> {
+    match
+        check_price_thresholds(
+            prices,
+            start_index,
+            up_percentage,
+            down_percentage,
+            price_decimals,
+            percentage_decimals
+        )
+    {
+        Some(_) => Some(true),
+        None if is_expired => Some(false),
+        None => None,
+    }
+}
+
+/// Price history of one market, starting at the price a game was created with.
+pub struct PriceSeries<'a> {
+    pub prices: &'a [u64],
+    pub timestamps: &'a [i64],
+    pub start_index: usize,
+}
+
+impl PriceSeries<'_> {
+    fn performance(&self, index: usize) -> Option<Decimal> {
+        Decimal::from(*self.prices.get(index)?).checked_div(
+            Decimal::from(*self.prices.get(self.start_index)?)
+        )
+    }
+}
+
+/// Relative games: `Some(true)` once market `a` outperforms market `b` by `up_percentage`,
+/// `Some(false)` once `b` outperforms `a` by `down_percentage`. Both histories are aligned on
+/// the union of their timestamps, each taking its latest price at or before every point.
+/// After `expires_at` the better performer at expiry wins, counting only prices recorded before
+/// expiry, and a tie stays `None`: the game is a draw.
+pub fn check_relative_performance(
+    a: &PriceSeries,
+    b: &PriceSeries,
+    up_percentage: u16,
+    down_percentage: u16,
+    percentage_decimals: u8,
+    expires_at: Option<i64>,
+    now: i64
+) -> Option<bool> {
+    if a.start_index >= a.prices.len() || b.start_index >= b.prices.len() {
+        return None;
+    }
+
+    let d_percentage_unit =
+        Decimal::from((10u32).pow(percentage_decimals as u32)) * Decimal::from(100);
+    let d_up_percentage = Decimal::from(up_percentage) / d_percentage_unit;
+    let d_down_percentage = Decimal::from(down_percentage) / d_percentage_unit;
+
+    let next_point = |i: usize, j: usize| -> Option<i64> {
+        match (a.timestamps.get(i + 1), b.timestamps.get(j + 1)) {
+            (Some(&ta), Some(&tb)) => Some(ta.min(tb)),
+            (Some(&ta), None) => Some(ta),
+            (None, Some(&tb)) => Some(tb),
+            (None, None) => None,
+        }
+    };
+    let advance = |i: &mut usize, j: &mut usize, point: i64| {
+        while a.timestamps.get(*i + 1).is_some_and(|&t| t <= point) {
+            *i += 1;
+        }
+        while b.timestamps.get(*j + 1).is_some_and(|&t| t <= point) {
+            *j += 1;
+        }
+    };
+
+    let (mut i, mut j) = (a.start_index, b.start_index);
+
+    while let Some(point) = next_point(i, j) {
+        if expires_at.is_some_and(|expires_at| point >= expires_at) {
+            break;
+        }
+        advance(&mut i, &mut j, point);
+
+        let spread = a.performance(i)? - b.performance(j)?;
+        if spread >= d_up_percentage {
+            return Some(true);
+        } else if spread <= -d_down_percentage {
+            return Some(false);
+        }
+    }
+
+    match expires_at {
+        Some(expires_at) if now >= expires_at => {}
+        _ => {
+            return None;
+        }
+    }
+
+    let spread = a.performance(i)? - b.performance(j)?;
+    if spread == Decimal::ZERO {
+        return None;
+    }
+    Some(spread > Decimal::ZERO)
+}
+
+/// Splits a pot of `2 * bet_size` into the (UP, DOWN) stakes so that each side risks in
+/// proportion to its chance of winning. For a driftless price the UP threshold is hit first
+/// with probability `down / (up + down)`, which keeps the expected value of both sides at zero.
+pub fn split_stakes(bet_size: u64, up_percentage: u16, down_percentage: u16) -> Option<(u64, u64)> {
+    let pot = (bet_size as u128).checked_mul(2)?;
+    let spread = (up_percentage as u128) + (down_percentage as u128);
+    if spread == 0 {
+        return None;
+    }
+
+    let up_stake = pot.checked_mul(down_percentage as u128)? / spread;
+    let down_stake = pot - up_stake;
+
+    Some((u64::try_from(up_stake).ok()?, u64::try_from(down_stake).ok()?))
+}
+
+/// Elo ratings of the winner and the loser of a game after it settles. The winner gains what
+/// the loser drops, more when the loser was the higher rated player.
+pub fn elo_ratings(winner: u16, loser: u16) -> (u16, u16) {
+    let difference = (winner as i32) - (loser as i32);
+    let steps = ELO_CHANGE_STEPS.partition_point(|&step| step <= difference);
+    let change = ELO_K_FACTOR - (steps as u16);
+
+    (winner.saturating_add(change), loser.saturating_sub(change))
+}
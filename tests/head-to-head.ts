@@ -12,7 +12,12 @@ import {
   THRESHOLD_DECIMALS,
   BET_SIZE,
 } from "./config";
-import { Connection, PublicKey } from "@solana/web3.js";
+import {
+  Connection,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+} from "@solana/web3.js";
 import {
   mockMintKeypair,
   mockPlayerAKeypair,
@@ -22,6 +27,8 @@ import {
   createMint,
   getOrCreateAssociatedTokenAccount,
   mintTo,
+  NATIVE_MINT,
+  TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import { airdrop, assertDeepEqual, D } from "./utils";
 import { Game } from "./types";
@@ -36,8 +43,18 @@ describe("Head to Head Game", () => {
     [Buffer.from("config")],
     program.programId
   );
+  const MARKET = 0;
+  const OTHER_MARKET = 1;
+
+  const marketSeed = (market: number) =>
+    new BN(market).toArrayLike(Buffer, "le", 2);
+
   const [pricesPda] = anchor.web3.PublicKey.findProgramAddressSync(
-    [Buffer.from("prices")],
+    [Buffer.from("prices"), marketSeed(MARKET)],
+    program.programId
+  );
+  const [otherPricesPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("prices"), marketSeed(OTHER_MARKET)],
     program.programId
   );
   const [gamesPda] = anchor.web3.PublicKey.findProgramAddressSync(
@@ -45,16 +62,72 @@ describe("Head to Head Game", () => {
     program.programId
   );
 
-  const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
-    [Buffer.from("vault")],
-    program.programId
-  );
+  const DEFAULT_TIER = {
+    upThresholdPercent: D_WIN_THRESHOLD_PERCENT,
+    downThresholdPercent: D_WIN_THRESHOLD_PERCENT,
+    joinThresholdPercent: D_JOIN_THRESHOLD_PERCENT,
+  };
+  const SWING_TIER = {
+    upThresholdPercent: D(10, THRESHOLD_DECIMALS),
+    downThresholdPercent: D(10, THRESHOLD_DECIMALS),
+    joinThresholdPercent: D(2, THRESHOLD_DECIMALS),
+  };
+  // +10% against -5%: UP is hit first a third of the time
+  const ODDS_TIER = {
+    upThresholdPercent: D(10, THRESHOLD_DECIMALS),
+    downThresholdPercent: D(5, THRESHOLD_DECIMALS),
+    joinThresholdPercent: D_JOIN_THRESHOLD_PERCENT,
+  };
+
+  const directional = (prediction: boolean, tier: number = 0) => ({
+    prediction,
+    mode: { directional: {} },
+    duration: null,
+    market: MARKET,
+    tier,
+    betSize: new BN(D_BET_SIZE),
+    partialFills: false,
+    ratingBand: null,
+    outcome: null,
+  });
+
+  const range = (breakout: boolean, duration: number) => ({
+    prediction: breakout,
+    mode: { range: {} },
+    duration: new BN(duration),
+    market: MARKET,
+    tier: 0,
+    betSize: new BN(D_BET_SIZE),
+    partialFills: false,
+    ratingBand: null,
+    outcome: null,
+  });
 
   let playerATokenAccount: PublicKey;
   let playerBTokenAccount: PublicKey;
 
   const mint = mockMintKeypair.publicKey;
 
+  const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), mint.toBuffer()],
+    program.programId
+  );
+  const [wagerMintPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("wager_mint"), mint.toBuffer()],
+    program.programId
+  );
+
+  const profilePda = (player: PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), player.toBuffer()],
+      program.programId
+    )[0];
+
+  const WAGER_LIMITS = {
+    minBetSize: new BN(D(1, MINT_DECIMALS)),
+    maxBetSize: new BN(D_BET_SIZE),
+  };
+
   async function credit(destination: PublicKey, amount: number) {
     await mintTo(
       connection,
@@ -95,17 +168,14 @@ describe("Head to Head Game", () => {
 
   it("successfully initializes config", async () => {
     const configArgs = {
-      betSize: new BN(D_BET_SIZE),
-      winThresholdPercent: D_WIN_THRESHOLD_PERCENT,
-      joinThresholdPercent: D_JOIN_THRESHOLD_PERCENT,
       thresholdDecimals: THRESHOLD_DECIMALS,
+      thresholdTiers: [DEFAULT_TIER],
     };
 
     await program.methods
       .initializeConfig(configArgs)
       .accounts({
         signer: SIGNER.publicKey,
-        mint,
       })
       .signers([SIGNER])
       .rpc();
@@ -115,21 +185,63 @@ describe("Head to Head Game", () => {
     expect(configAccount.admin.toString()).to.equal(
       SIGNER.publicKey.toString()
     );
-    expect(configAccount.mint.toString()).to.equal(mint.toString());
-    expect(configAccount.betSize.toNumber()).to.equal(D_BET_SIZE);
     expect(configAccount.thresholdDecimals).to.equal(THRESHOLD_DECIMALS);
-    expect(configAccount.joinThresholdPercent).to.equal(
-      D_JOIN_THRESHOLD_PERCENT
-    );
-    expect(configAccount.winThresholdPercent).to.equal(D_WIN_THRESHOLD_PERCENT);
+    expect(configAccount.thresholdTiers).to.deep.equal([DEFAULT_TIER]);
+  });
+
+  it("allows admin to configure threshold tiers", async () => {
+    await program.methods
+      .setThresholdTiers([DEFAULT_TIER, SWING_TIER, ODDS_TIER])
+      .accounts({ admin: SIGNER.publicKey })
+      .signers([SIGNER])
+      .rpc();
+
+    const configAccount = await program.account.config.fetch(configPda);
+    expect(configAccount.thresholdTiers).to.deep.equal([
+      DEFAULT_TIER,
+      SWING_TIER,
+      ODDS_TIER,
+    ]);
+  });
+
+  it("prevents non-admin from configuring threshold tiers", async () => {
+    try {
+      await program.methods
+        .setThresholdTiers([SWING_TIER])
+        .accounts({ admin: mockPlayerAKeypair.publicKey })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not allow non-admin to configure tiers");
+    } catch (error) {
+      expect(error.message).to.include("Only admin can perform this action");
+    }
+  });
+
+  it("rejects tiers with join threshold above win threshold", async () => {
+    try {
+      await program.methods
+        .setThresholdTiers([
+          {
+            upThresholdPercent: D_WIN_THRESHOLD_PERCENT,
+            downThresholdPercent: D_JOIN_THRESHOLD_PERCENT,
+            joinThresholdPercent: D_WIN_THRESHOLD_PERCENT,
+          },
+        ])
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc();
+
+      assert.fail("Should not allow invalid tiers");
+    } catch (error) {
+      expect(error.message).to.include("InvalidThresholdTiers");
+    }
   });
 
   it("prevents double initialization", async () => {
     const configArgs = {
-      betSize: new BN(D_BET_SIZE),
-      winThresholdPercent: D_WIN_THRESHOLD_PERCENT,
-      joinThresholdPercent: D_JOIN_THRESHOLD_PERCENT,
       thresholdDecimals: THRESHOLD_DECIMALS,
+      thresholdTiers: [DEFAULT_TIER],
     };
 
     try {
@@ -138,7 +250,6 @@ describe("Head to Head Game", () => {
         .initializeConfig(configArgs)
         .accounts({
           signer: SIGNER.publicKey,
-          mint,
         })
         .signers([SIGNER])
         .rpc();
@@ -153,6 +264,7 @@ describe("Head to Head Game", () => {
     const pricesArgs = {
       initialPrice: new BN(D(1500, PRICE_DECIMALS)),
       priceDecimals: PRICE_DECIMALS,
+      market: MARKET,
     };
 
     await program.methods
@@ -171,10 +283,31 @@ describe("Head to Head Game", () => {
     expect(decimals).to.equal(PRICE_DECIMALS);
   });
 
+  it("prevents non-admin from initializing prices", async () => {
+    try {
+      await program.methods
+        .initializePrices({
+          initialPrice: new BN(D(1, PRICE_DECIMALS)),
+          priceDecimals: 0,
+          market: 99,
+        })
+        .accounts({
+          signer: mockPlayerAKeypair.publicKey,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not allow non-admin to initialize prices");
+    } catch (error) {
+      expect(error.message).to.include("AdminOnly");
+    }
+  });
+
   it("prevents double initialization of prices", async () => {
     const pricesArgs = {
       initialPrice: new BN(D(1500, PRICE_DECIMALS)),
       priceDecimals: PRICE_DECIMALS,
+      market: MARKET,
     };
 
     try {
@@ -224,10 +357,11 @@ describe("Head to Head Game", () => {
 
   it("successfully initializes vault", async () => {
     await program.methods
-      .initializeVault()
+      .initializeVault(WAGER_LIMITS)
       .accounts({
         signer: SIGNER.publicKey,
         mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([SIGNER])
       .rpc();
@@ -236,10 +370,11 @@ describe("Head to Head Game", () => {
   it("prevents double initialization of vault", async () => {
     try {
       await program.methods
-        .initializeVault()
+        .initializeVault(WAGER_LIMITS)
         .accounts({
           signer: SIGNER.publicKey,
           mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([SIGNER])
         .rpc();
@@ -257,7 +392,7 @@ describe("Head to Head Game", () => {
     );
 
     for (const price of newPrices) {
-      await program.methods.addPrice(price).signers([SIGNER]).rpc();
+      await program.methods.addPrice(MARKET, price).signers([SIGNER]).rpc();
     }
 
     const updatedPriceAccount = await program.account.prices.fetch(pricesPda);
@@ -265,6 +400,9 @@ describe("Head to Head Game", () => {
     expect(updatedPriceAccount.prices.length).to.equal(
       priceAccount.prices.length + newPrices.length
     );
+    expect(updatedPriceAccount.timestamps.length).to.equal(
+      updatedPriceAccount.prices.length
+    );
 
     updatedPriceAccount.prices.forEach((storedPrice, index) => {
       expect(storedPrice.toString()).to.equal(
@@ -279,7 +417,7 @@ describe("Head to Head Game", () => {
 
     try {
       await program.methods
-        .addPrice(price)
+        .addPrice(MARKET, price)
         .accounts({ admin: nonAdmin.publicKey })
         .signers([nonAdmin])
         .rpc();
@@ -294,7 +432,10 @@ describe("Head to Head Game", () => {
     const invalidPrice = new BN(0);
 
     try {
-      await program.methods.addPrice(invalidPrice).signers([SIGNER]).rpc();
+      await program.methods
+        .addPrice(MARKET, invalidPrice)
+        .signers([SIGNER])
+        .rpc();
 
       assert.fail("Should not allow invalid price");
     } catch (error) {
@@ -317,10 +458,13 @@ describe("Head to Head Game", () => {
     expect(D(playerBalanceBefore.value.uiAmount)).to.equal(D_BET_SIZE);
 
     await program.methods
-      .createGame(true)
+      .createGame(directional(true))
       .accounts({
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc({ commitment: "confirmed" });
@@ -338,6 +482,14 @@ describe("Head to Head Game", () => {
       priceIndex: priceAccount.prices.length - 1,
       result: null,
       isClosed: false,
+      mode: { directional: {} },
+      expiresAt: null,
+      market: MARKET,
+      opponentMarket: null,
+      tier: 0,
+      thresholds: DEFAULT_TIER,
+      opponentAmount: new BN(D_BET_SIZE),
+      mint,
     };
 
     assertDeepEqual(game, expectedGame);
@@ -360,10 +512,13 @@ describe("Head to Head Game", () => {
     const priceAccount = await program.account.prices.fetch(pricesPda);
 
     await program.methods
-      .createGame(false)
+      .createGame(directional(false))
       .accounts({
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc({ commitment: "confirmed" });
@@ -393,6 +548,8 @@ describe("Head to Head Game", () => {
         .accounts({
           player: mockPlayerBKeypair.publicKey,
           playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerBKeypair])
         .rpc({ commitment: "confirmed" });
@@ -421,6 +578,8 @@ describe("Head to Head Game", () => {
       .accounts({
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc({ commitment: "confirmed" });
@@ -446,6 +605,8 @@ describe("Head to Head Game", () => {
         .accounts({
           player: mockPlayerAKeypair.publicKey,
           playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerAKeypair])
         .rpc({ commitment: "confirmed" });
@@ -461,8 +622,12 @@ describe("Head to Head Game", () => {
       await program.methods
         .joinGame(1)
         .accounts({
+          prices: pricesPda,
+          authority: mockPlayerAKeypair.publicKey,
           player: mockPlayerAKeypair.publicKey,
           playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerAKeypair])
         .rpc();
@@ -478,8 +643,12 @@ describe("Head to Head Game", () => {
       await program.methods
         .joinGame(0)
         .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
           player: mockPlayerBKeypair.publicKey,
           playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerBKeypair])
         .rpc();
@@ -500,8 +669,12 @@ describe("Head to Head Game", () => {
     await program.methods
       .joinGame(game_index)
       .accounts({
+        prices: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
         player: mockPlayerBKeypair.publicKey,
         playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerBKeypair])
       .rpc({ commitment: "confirmed" });
@@ -528,8 +701,12 @@ describe("Head to Head Game", () => {
       await program.methods
         .joinGame(1)
         .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
           player: mockPlayerBKeypair.publicKey,
           playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerBKeypair])
         .rpc();
@@ -547,6 +724,8 @@ describe("Head to Head Game", () => {
         .accounts({
           player: mockPlayerAKeypair.publicKey,
           playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerAKeypair])
         .rpc();
@@ -561,10 +740,13 @@ describe("Head to Head Game", () => {
     await credit(playerATokenAccount, D_BET_SIZE);
 
     await program.methods
-      .createGame(true)
+      .createGame(directional(true))
       .accounts({
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc();
@@ -577,15 +759,19 @@ describe("Head to Head Game", () => {
 
     const newPrice = gamePrice.mul(new BN(101)).div(new BN(100));
 
-    await program.methods.addPrice(newPrice).signers([SIGNER]).rpc();
+    await program.methods.addPrice(MARKET, newPrice).signers([SIGNER]).rpc();
 
     await credit(playerBTokenAccount, D_BET_SIZE);
     try {
       await program.methods
         .joinGame(gameIndex)
         .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
           player: mockPlayerBKeypair.publicKey,
           playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerBKeypair])
         .rpc();
@@ -601,8 +787,12 @@ describe("Head to Head Game", () => {
       await program.methods
         .claimWinnings(999)
         .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerAKeypair.publicKey,
           player: mockPlayerAKeypair.publicKey,
           playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerAKeypair])
         .rpc();
@@ -616,10 +806,13 @@ describe("Head to Head Game", () => {
   it("prevents claiming from game without opponent", async () => {
     await credit(playerATokenAccount, D_BET_SIZE);
     await program.methods
-      .createGame(true)
+      .createGame(directional(true))
       .accounts({
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc();
@@ -631,8 +824,12 @@ describe("Head to Head Game", () => {
       await program.methods
         .claimWinnings(gameIndex)
         .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerAKeypair.publicKey,
           player: mockPlayerAKeypair.publicKey,
           playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerAKeypair])
         .rpc();
@@ -650,8 +847,12 @@ describe("Head to Head Game", () => {
       await program.methods
         .claimWinnings(gameIndex)
         .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerAKeypair.publicKey,
           player: mockPlayerAKeypair.publicKey,
           playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerAKeypair])
         .rpc();
@@ -665,10 +866,13 @@ describe("Head to Head Game", () => {
   it("prevents claiming when price threshold not reached", async () => {
     await credit(playerATokenAccount, D_BET_SIZE);
     await program.methods
-      .createGame(true) // Host predicts UP
+      .createGame(directional(true)) // Host predicts UP
       .accounts({
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc();
@@ -681,8 +885,12 @@ describe("Head to Head Game", () => {
     await program.methods
       .joinGame(gameIndex)
       .accounts({
+        prices: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
         player: mockPlayerBKeypair.publicKey,
         playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerBKeypair])
       .rpc();
@@ -693,15 +901,19 @@ describe("Head to Head Game", () => {
     const gamePrice = priceAccount.prices[game.priceIndex];
 
     const newPrice = gamePrice.mul(new BN(103)).div(new BN(100));
-    await program.methods.addPrice(newPrice).signers([SIGNER]).rpc();
+    await program.methods.addPrice(MARKET, newPrice).signers([SIGNER]).rpc();
 
     // Host tries to claim before threshold is reached
     try {
       await program.methods
         .claimWinnings(gameIndex)
         .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerAKeypair.publicKey,
           player: mockPlayerAKeypair.publicKey,
           playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerAKeypair])
         .rpc();
@@ -718,8 +930,12 @@ describe("Head to Head Game", () => {
       await program.methods
         .claimWinnings(gameIndex)
         .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
           player: mockPlayerBKeypair.publicKey,
           playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerBKeypair])
         .rpc();
@@ -735,10 +951,13 @@ describe("Head to Head Game", () => {
   it("prevents non-winner host from claiming when price threshold is reached", async () => {
     await credit(playerATokenAccount, D_BET_SIZE);
     await program.methods
-      .createGame(true) // Host predicts UP
+      .createGame(directional(true)) // Host predicts UP
       .accounts({
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc();
@@ -751,8 +970,12 @@ describe("Head to Head Game", () => {
     await program.methods
       .joinGame(gameIndex)
       .accounts({
+        prices: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
         player: mockPlayerBKeypair.publicKey,
         playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerBKeypair])
       .rpc();
@@ -763,15 +986,19 @@ describe("Head to Head Game", () => {
     const gamePrice = priceAccount.prices[game.priceIndex];
 
     const newPrice = gamePrice.mul(new BN(95)).div(new BN(100)); // 5% decrease
-    await program.methods.addPrice(newPrice).signers([SIGNER]).rpc();
+    await program.methods.addPrice(MARKET, newPrice).signers([SIGNER]).rpc();
 
     // Host (Player A) tries to claim despite losing
     try {
       await program.methods
         .claimWinnings(gameIndex)
         .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerAKeypair.publicKey,
           player: mockPlayerAKeypair.publicKey,
           playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerAKeypair])
         .rpc();
@@ -785,10 +1012,13 @@ describe("Head to Head Game", () => {
   it("prevents non-winner opponent from claiming when price threshold is reached", async () => {
     await credit(playerATokenAccount, D_BET_SIZE);
     await program.methods
-      .createGame(true) // Host predicts UP
+      .createGame(directional(true)) // Host predicts UP
       .accounts({
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc();
@@ -801,8 +1031,12 @@ describe("Head to Head Game", () => {
     await program.methods
       .joinGame(gameIndex)
       .accounts({
+        prices: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
         player: mockPlayerBKeypair.publicKey,
         playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerBKeypair])
       .rpc();
@@ -813,15 +1047,19 @@ describe("Head to Head Game", () => {
     const gamePrice = priceAccount.prices[game.priceIndex];
 
     const newPrice = gamePrice.mul(new BN(105)).div(new BN(100)); // 5% increase
-    await program.methods.addPrice(newPrice).signers([SIGNER]).rpc();
+    await program.methods.addPrice(MARKET, newPrice).signers([SIGNER]).rpc();
 
     // Opponent (Player B) tries to claim despite losing
     try {
       await program.methods
         .claimWinnings(gameIndex)
         .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
           player: mockPlayerBKeypair.publicKey,
           playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([mockPlayerBKeypair])
         .rpc();
@@ -849,10 +1087,13 @@ describe("Head to Head Game", () => {
     );
 
     await program.methods
-      .createGame(true)
+      .createGame(directional(true))
       .accounts({
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc({ commitment: "confirmed" });
@@ -880,8 +1121,12 @@ describe("Head to Head Game", () => {
     await program.methods
       .joinGame(gameIndex)
       .accounts({
+        prices: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
         player: mockPlayerBKeypair.publicKey,
         playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerBKeypair])
       .rpc({ commitment: "confirmed" });
@@ -902,16 +1147,49 @@ describe("Head to Head Game", () => {
 
     const newPrice = gamePrice.mul(new BN(106)).div(new BN(100)); // 5% increase
     await program.methods
-      .addPrice(newPrice)
+      .addPrice(MARKET, newPrice)
       .signers([SIGNER])
       .rpc({ commitment: "confirmed" });
 
+    const hostProfile = await program.account.playerProfile.fetch(
+      profilePda(mockPlayerAKeypair.publicKey)
+    );
+    const opponentProfile = await program.account.playerProfile.fetch(
+      profilePda(mockPlayerBKeypair.publicKey)
+    );
+
+    // The loss has to be recorded in the loser's profile
+    try {
+      await program.methods
+        .claimWinnings(gameIndex)
+        .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          opponentProfile: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not settle without the loser's profile");
+    } catch (error) {
+      expect(error.message).to.include("InvalidProfile");
+    }
+
     // Host claims winnings
     await program.methods
       .claimWinnings(gameIndex)
       .accounts({
+        pricesAccount: pricesPda,
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc({ commitment: "confirmed" });
@@ -938,6 +1216,74 @@ describe("Head to Head Game", () => {
     const finalGame = finalGamesAccount.games[gameIndex];
     expect(finalGame.isClosed).to.be.true;
     expect(finalGame.result).to.be.true;
+
+    // Lifetime stats of both players
+    const finalHostProfile = await program.account.playerProfile.fetch(
+      profilePda(mockPlayerAKeypair.publicKey)
+    );
+    const finalOpponentProfile = await program.account.playerProfile.fetch(
+      profilePda(mockPlayerBKeypair.publicKey)
+    );
+    expect(finalHostProfile.wins).to.equal(hostProfile.wins + 1);
+    expect(finalHostProfile.currentStreak).to.equal(hostProfile.currentStreak + 1);
+    expect(finalHostProfile.totalWon.sub(hostProfile.totalWon).toNumber()).to.equal(
+      2 * D_BET_SIZE
+    );
+    expect(finalOpponentProfile.losses).to.equal(opponentProfile.losses + 1);
+    expect(finalOpponentProfile.currentStreak).to.equal(0);
+    expect(finalHostProfile.rating).to.be.greaterThan(hostProfile.rating);
+    expect(finalOpponentProfile.rating).to.be.lessThan(opponentProfile.rating);
+  });
+
+  it("restricts joining to opponents within the rating band", async () => {
+    await credit(playerATokenAccount, D_BET_SIZE);
+    await credit(playerBTokenAccount, D_BET_SIZE);
+
+    await program.methods
+      .createGame({ ...directional(true), ratingBand: 1 })
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+    expect(gamesAccount.games[gameIndex].ratingBand).to.equal(1);
+
+    try {
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc();
+
+      assert.fail("Should not join outside the rating band");
+    } catch (error) {
+      expect(error.message).to.include("RatingOutOfBand");
+    }
+
+    await program.methods
+      .withdrawFromGame(gameIndex)
+      .accounts({
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
   });
 
   it("allows winning opponent to successfully claim", async () => {
@@ -955,10 +1301,13 @@ describe("Head to Head Game", () => {
     );
 
     await program.methods
-      .createGame(true)
+      .createGame(directional(true))
       .accounts({
+        authority: mockPlayerAKeypair.publicKey,
         player: mockPlayerAKeypair.publicKey,
         playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerAKeypair])
       .rpc({ commitment: "confirmed" });
@@ -986,8 +1335,12 @@ describe("Head to Head Game", () => {
     await program.methods
       .joinGame(gameIndex)
       .accounts({
+        prices: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
         player: mockPlayerBKeypair.publicKey,
         playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerBKeypair])
       .rpc({ commitment: "confirmed" });
@@ -1008,7 +1361,7 @@ describe("Head to Head Game", () => {
 
     const newPrice = gamePrice.mul(new BN(94)).div(new BN(100)); // 5% increase
     await program.methods
-      .addPrice(newPrice)
+      .addPrice(MARKET, newPrice)
       .signers([SIGNER])
       .rpc({ commitment: "confirmed" });
 
@@ -1016,8 +1369,13 @@ describe("Head to Head Game", () => {
     await program.methods
       .claimWinnings(gameIndex)
       .accounts({
+        opponentProfile: profilePda(mockPlayerAKeypair.publicKey),
+        pricesAccount: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
         player: mockPlayerBKeypair.publicKey,
         playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([mockPlayerBKeypair])
       .rpc({ commitment: "confirmed" });
@@ -1044,4 +1402,2973 @@ describe("Head to Head Game", () => {
     expect(finalGame.isClosed).to.be.true;
     expect(finalGame.result).to.be.false;
   });
+
+  it("rejects range game without duration", async () => {
+    await credit(playerATokenAccount, D_BET_SIZE);
+
+    try {
+      await program.methods
+        .createGame({
+          prediction: true,
+          mode: { range: {} },
+          duration: null,
+          market: MARKET,
+          tier: 0,
+          betSize: new BN(D_BET_SIZE),
+          partialFills: false,
+          ratingBand: null,
+          outcome: null,
+        })
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not allow range game without duration");
+    } catch (error) {
+      expect(error.message).to.include("InvalidGameDuration");
+    }
+  });
+
+  it("settles range game in favour of breakout side", async () => {
+    await credit(playerBTokenAccount, D_BET_SIZE);
+
+    await program.methods
+      .createGame(range(true, 3600))
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+    const game = gamesAccount.games[gameIndex];
+    expect(game.mode).to.deep.equal({ range: {} });
+    expect(game.expiresAt).to.not.be.null;
+
+    await program.methods
+      .joinGame(gameIndex)
+      .accounts({
+        prices: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const priceAccount = await program.account.prices.fetch(pricesPda);
+    const gamePrice = priceAccount.prices[game.priceIndex];
+
+    // Price stays inside the band, game runs until expiry
+    await program.methods
+      .addPrice(MARKET, gamePrice.mul(new BN(97)).div(new BN(100)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await program.methods
+        .claimWinnings(gameIndex)
+        .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc();
+
+      assert.fail("Should not settle range game before expiry");
+    } catch (error) {
+      expect(error.message).to.include("GameNotFinished");
+    }
+
+    // 5% drop breaks the band
+    await program.methods
+      .addPrice(MARKET, gamePrice.mul(new BN(95)).div(new BN(100)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    const hostBalanceBefore = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+
+    await program.methods
+      .claimWinnings(gameIndex)
+      .accounts({
+        opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+        pricesAccount: pricesPda,
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const hostBalanceAfter = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+    expect(hostBalanceAfter.value.uiAmount).to.equal(
+      hostBalanceBefore.value.uiAmount + BET_SIZE * 2
+    );
+
+    const finalGamesAccount = await program.account.games.fetch(gamesPda);
+    const finalGame = finalGamesAccount.games[gameIndex];
+    expect(finalGame.isClosed).to.be.true;
+    expect(finalGame.result).to.be.true;
+  });
+
+  it("settles relative game for the outperforming market", async () => {
+    await program.methods
+      .initializePrices({
+        initialPrice: new BN(D(20, PRICE_DECIMALS)),
+        priceDecimals: PRICE_DECIMALS,
+        market: OTHER_MARKET,
+      })
+      .accounts({
+        signer: SIGNER.publicKey,
+      })
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    await credit(playerATokenAccount, D_BET_SIZE);
+    await credit(playerBTokenAccount, D_BET_SIZE);
+
+    // Host backs MARKET against OTHER_MARKET
+    await program.methods
+      .createGame({
+        prediction: true,
+        mode: { relative: {} },
+        duration: null,
+        market: MARKET,
+        tier: 0,
+        betSize: new BN(D_BET_SIZE),
+        partialFills: false,
+        ratingBand: null,
+        outcome: null,
+      })
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        opponentPricesAccount: otherPricesPda,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+    const game = gamesAccount.games[gameIndex];
+    expect(game.opponentMarket).to.equal(OTHER_MARKET);
+
+    try {
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          prices: pricesPda,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc();
+
+      assert.fail("Should require the opponent market prices");
+    } catch (error) {
+      expect(error.message).to.include("InvalidPricesAccount");
+    }
+
+    await program.methods
+      .joinGame(gameIndex)
+      .accounts({
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        prices: pricesPda,
+        opponentPrices: otherPricesPda,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    // MARKET +3%, OTHER_MARKET -3%: MARKET outperforms by 6%
+    const priceAccount = await program.account.prices.fetch(pricesPda);
+    const otherPriceAccount = await program.account.prices.fetch(
+      otherPricesPda
+    );
+    await program.methods
+      .addPrice(
+        MARKET,
+        priceAccount.prices[game.priceIndex].mul(new BN(103)).div(new BN(100))
+      )
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .addPrice(
+        OTHER_MARKET,
+        otherPriceAccount.prices[game.opponentPriceIndex]
+          .mul(new BN(97))
+          .div(new BN(100))
+      )
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await program.methods
+        .claimWinnings(gameIndex)
+        .accounts({
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          pricesAccount: pricesPda,
+          opponentPricesAccount: otherPricesPda,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc();
+
+      assert.fail("Should not allow opponent to claim");
+    } catch (error) {
+      expect(error.message).to.include("SignerNotWinner");
+    }
+
+    await program.methods
+      .claimWinnings(gameIndex)
+      .accounts({
+        opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        pricesAccount: pricesPda,
+        opponentPricesAccount: otherPricesPda,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const finalGamesAccount = await program.account.games.fetch(gamesPda);
+    expect(finalGamesAccount.games[gameIndex].isClosed).to.be.true;
+    expect(finalGamesAccount.games[gameIndex].result).to.be.true;
+  });
+
+  it("rejects unknown threshold tier", async () => {
+    await credit(playerATokenAccount, D_BET_SIZE);
+
+    try {
+      await program.methods
+        .createGame(directional(true, 7))
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not allow unknown tier");
+    } catch (error) {
+      expect(error.message).to.include("InvalidThresholdTier");
+    }
+  });
+
+  it("settles game with the thresholds of its tier", async () => {
+    await credit(playerBTokenAccount, D_BET_SIZE);
+
+    await program.methods
+      .createGame(directional(true, 1))
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+    const game = gamesAccount.games[gameIndex];
+    expect(game.tier).to.equal(1);
+    expect(game.thresholds).to.deep.equal(SWING_TIER);
+
+    // 1.5% is above the default join threshold but within the swing tier's
+    const priceAccount = await program.account.prices.fetch(pricesPda);
+    const gamePrice = priceAccount.prices[game.priceIndex];
+    await program.methods
+      .addPrice(MARKET, gamePrice.mul(new BN(1015)).div(new BN(1000)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    await program.methods
+      .joinGame(gameIndex)
+      .accounts({
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        prices: pricesPda,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    // 6% move settles default tier games but not the 10% swing tier
+    await program.methods
+      .addPrice(MARKET, gamePrice.mul(new BN(106)).div(new BN(100)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await program.methods
+        .claimWinnings(gameIndex)
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          pricesAccount: pricesPda,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not settle before the tier threshold");
+    } catch (error) {
+      expect(error.message).to.include("GameNotFinished");
+    }
+
+    await program.methods
+      .addPrice(MARKET, gamePrice.mul(new BN(110)).div(new BN(100)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    await program.methods
+      .claimWinnings(gameIndex)
+      .accounts({
+        opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        pricesAccount: pricesPda,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const finalGamesAccount = await program.account.games.fetch(gamesPda);
+    expect(finalGamesAccount.games[gameIndex].result).to.be.true;
+  });
+
+  it("scales stakes to the odds of asymmetric thresholds", async () => {
+    const hostStake = Math.floor((D_BET_SIZE * 2) / 3);
+    const opponentStake = D_BET_SIZE * 2 - hostStake;
+
+    await credit(playerATokenAccount, hostStake);
+    await credit(playerBTokenAccount, opponentStake);
+
+    const hostBalanceBefore = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+    const opponentBalanceBefore = await connection.getTokenAccountBalance(
+      playerBTokenAccount
+    );
+
+    // Host takes the +10% side, opponent the -5% side
+    await program.methods
+      .createGame(directional(true, 2))
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+    const game = gamesAccount.games[gameIndex];
+    expect(game.amount.toNumber()).to.equal(hostStake);
+    expect(game.opponentAmount.toNumber()).to.equal(opponentStake);
+
+    await program.methods
+      .joinGame(gameIndex)
+      .accounts({
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        prices: pricesPda,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const opponentBalanceAfterJoin = await connection.getTokenAccountBalance(
+      playerBTokenAccount
+    );
+    expect(
+      D(
+        opponentBalanceBefore.value.uiAmount -
+          opponentBalanceAfterJoin.value.uiAmount
+      )
+    ).to.be.closeTo(opponentStake, 1);
+
+    // -6% crosses the down threshold only
+    const priceAccount = await program.account.prices.fetch(pricesPda);
+    const gamePrice = priceAccount.prices[game.priceIndex];
+    await program.methods
+      .addPrice(MARKET, gamePrice.mul(new BN(94)).div(new BN(100)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    await program.methods
+      .claimWinnings(gameIndex)
+      .accounts({
+        opponentProfile: profilePda(mockPlayerAKeypair.publicKey),
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        pricesAccount: pricesPda,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const hostBalanceAfter = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+    const opponentBalanceAfter = await connection.getTokenAccountBalance(
+      playerBTokenAccount
+    );
+    expect(
+      D(hostBalanceBefore.value.uiAmount - hostBalanceAfter.value.uiAmount)
+    ).to.be.closeTo(hostStake, 1);
+    expect(
+      D(
+        opponentBalanceAfter.value.uiAmount -
+          opponentBalanceBefore.value.uiAmount
+      )
+    ).to.be.closeTo(hostStake, 1);
+  });
+
+  it("rejects a bucket game without an outcome", async () => {
+    try {
+      await program.methods
+        .createGame({ ...directional(true), mode: { buckets: {} } })
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should require the host's bucket");
+    } catch (error) {
+      expect(error.message).to.include("InvalidOutcome");
+    }
+  });
+
+  it("settles a bucket game on the bucket the price lands in first", async () => {
+    // The big UP bucket pays four times the host stake
+    const hostStake = D_BET_SIZE;
+    const opponentStake = D_BET_SIZE * 4;
+    await credit(playerATokenAccount, hostStake);
+    await credit(playerBTokenAccount, opponentStake);
+
+    await program.methods
+      .createGame({
+        ...directional(false),
+        mode: { buckets: {} },
+        outcome: { upBig: {} },
+      })
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+    const game = gamesAccount.games[gameIndex];
+    expect(game.hostPrediction).to.equal(true);
+    expect(game.outcome).to.deep.equal({ upBig: {} });
+    expect(game.amount.toNumber()).to.equal(hostStake);
+    expect(game.opponentAmount.toNumber()).to.equal(opponentStake);
+
+    await program.methods
+      .joinGame(gameIndex)
+      .accounts({
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        prices: pricesPda,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    // +6% lands in the small UP bucket first, the later +12% does not count
+    const priceAccount = await program.account.prices.fetch(pricesPda);
+    const gamePrice = priceAccount.prices[game.priceIndex];
+    for (const percent of [106, 112]) {
+      await program.methods
+        .addPrice(MARKET, gamePrice.mul(new BN(percent)).div(new BN(100)))
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    try {
+      await program.methods
+        .claimWinnings(gameIndex)
+        .accounts({
+          opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          pricesAccount: pricesPda,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not pay the host when another bucket came first");
+    } catch (error) {
+      expect(error.message).to.include("SignerNotWinner");
+    }
+
+    const opponentBalanceBefore = await connection.getTokenAccountBalance(
+      playerBTokenAccount
+    );
+
+    await program.methods
+      .claimWinnings(gameIndex)
+      .accounts({
+        opponentProfile: profilePda(mockPlayerAKeypair.publicKey),
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        pricesAccount: pricesPda,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const opponentBalanceAfter = await connection.getTokenAccountBalance(
+      playerBTokenAccount
+    );
+    expect(
+      D(
+        opponentBalanceAfter.value.uiAmount -
+          opponentBalanceBefore.value.uiAmount
+      )
+    ).to.be.closeTo(hostStake + opponentStake, 1);
+  });
+
+  it("prevents non-admin from registering a wager mint", async () => {
+    const otherMint = await createMint(
+      connection,
+      SIGNER,
+      SIGNER.publicKey,
+      null,
+      MINT_DECIMALS
+    );
+
+    try {
+      await program.methods
+        .initializeVault(WAGER_LIMITS)
+        .accounts({
+          signer: mockPlayerAKeypair.publicKey,
+          mint: otherMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not allow non-admin to register a mint");
+    } catch (error) {
+      expect(error.message).to.include("Only admin can perform this action");
+    }
+  });
+
+  it("rejects bet size outside the limits of the mint", async () => {
+    await credit(playerATokenAccount, D_BET_SIZE * 2);
+
+    try {
+      await program.methods
+        .createGame({
+          ...directional(true),
+          betSize: new BN(D_BET_SIZE * 2),
+        })
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not allow bet above the mint limit");
+    } catch (error) {
+      expect(error.message).to.include("InvalidBetSize");
+    }
+  });
+
+  it("prevents new games on a disabled mint", async () => {
+    await program.methods
+      .updateWagerMint(WAGER_LIMITS, false)
+      .accounts({ admin: SIGNER.publicKey, wagerMint: wagerMintPda })
+      .signers([SIGNER])
+      .rpc();
+
+    const wagerMint = await program.account.wagerMint.fetch(wagerMintPda);
+    expect(wagerMint.isEnabled).to.equal(false);
+
+    try {
+      await program.methods
+        .createGame(directional(true))
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not allow games on a disabled mint");
+    } catch (error) {
+      expect(error.message).to.include("MintDisabled");
+    }
+
+    await program.methods
+      .updateWagerMint(WAGER_LIMITS, true)
+      .accounts({ admin: SIGNER.publicKey, wagerMint: wagerMintPda })
+      .signers([SIGNER])
+      .rpc();
+  });
+
+  it("keeps games of each mint in the vault of that mint", async () => {
+    const otherMint = await createMint(
+      connection,
+      SIGNER,
+      SIGNER.publicKey,
+      null,
+      MINT_DECIMALS
+    );
+    const [otherVaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), otherMint.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeVault(WAGER_LIMITS)
+      .accounts({
+        signer: SIGNER.publicKey,
+        mint: otherMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([SIGNER])
+      .rpc();
+
+    const hostAccount = await getOrCreateAssociatedTokenAccount(
+      connection,
+      SIGNER,
+      otherMint,
+      mockPlayerAKeypair.publicKey
+    );
+    const opponentAccount = await getOrCreateAssociatedTokenAccount(
+      connection,
+      SIGNER,
+      otherMint,
+      mockPlayerBKeypair.publicKey
+    );
+    await mintTo(
+      connection,
+      SIGNER,
+      otherMint,
+      hostAccount.address,
+      SIGNER,
+      D_BET_SIZE
+    );
+    await mintTo(
+      connection,
+      SIGNER,
+      otherMint,
+      opponentAccount.address,
+      SIGNER,
+      D_BET_SIZE
+    );
+
+    const vaultBalanceBefore = await connection.getTokenAccountBalance(
+      vaultPda
+    );
+
+    await program.methods
+      .createGame(directional(true))
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: hostAccount.address,
+        mint: otherMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+    expect(gamesAccount.games[gameIndex].mint.toString()).to.equal(
+      otherMint.toString()
+    );
+
+    await credit(playerBTokenAccount, D_BET_SIZE);
+    try {
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          prices: pricesPda,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc();
+
+      assert.fail("Should not allow joining with another mint");
+    } catch (error) {
+      expect(error.message).to.include("InvalidMint");
+    }
+
+    await program.methods
+      .joinGame(gameIndex)
+      .accounts({
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: opponentAccount.address,
+        mint: otherMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        prices: pricesPda,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const otherVaultBalance = await connection.getTokenAccountBalance(
+      otherVaultPda
+    );
+    const vaultBalanceAfter = await connection.getTokenAccountBalance(vaultPda);
+    expect(D(otherVaultBalance.value.uiAmount)).to.equal(D_BET_SIZE * 2);
+    expect(vaultBalanceAfter.value.amount).to.equal(
+      vaultBalanceBefore.value.amount
+    );
+  });
+
+  it("claims several won games in one transaction", async () => {
+    const gameIndices: number[] = [];
+
+    for (let i = 0; i < 2; i++) {
+      await credit(playerATokenAccount, D_BET_SIZE);
+      await credit(playerBTokenAccount, D_BET_SIZE);
+
+      await program.methods
+        .createGame(directional(true))
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gamesAccount = await program.account.games.fetch(gamesPda);
+      const gameIndex = gamesAccount.games.length - 1;
+      gameIndices.push(gameIndex);
+
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    const priceAccount = await program.account.prices.fetch(pricesPda);
+    const lastPrice = priceAccount.prices[priceAccount.prices.length - 1];
+    await program.methods
+      .addPrice(MARKET, lastPrice.mul(new BN(106)).div(new BN(100)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    const loserProfile = {
+      pubkey: profilePda(mockPlayerBKeypair.publicKey),
+      isWritable: true,
+      isSigner: false,
+    };
+    const claimMany = (indices: number[], extraAccounts = []) =>
+      program.methods
+        .claimMany(indices)
+        .accounts({
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: pricesPda, isWritable: false, isSigner: false },
+          loserProfile,
+          ...extraAccounts,
+        ])
+        .signers([mockPlayerAKeypair]);
+
+    try {
+      await claimMany(gameIndices, [loserProfile]).rpc();
+
+      assert.fail("Should not claim with a duplicated account");
+    } catch (error) {
+      expect(error.message).to.include("DuplicateAccount");
+    }
+
+    try {
+      await claimMany([...gameIndices, 999]).rpc();
+
+      assert.fail("Should not claim a batch with an invalid game");
+    } catch (error) {
+      expect(error.message).to.include("GameNotFound");
+      expect(error.logs.join("\n")).to.include("Claim of game 999 failed");
+    }
+
+    const hostBalanceBefore = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+    const lossesBefore = (
+      await program.account.playerProfile.fetch(
+        profilePda(mockPlayerBKeypair.publicKey)
+      )
+    ).losses;
+
+    await claimMany(gameIndices).rpc({ commitment: "confirmed" });
+
+    const hostBalanceAfter = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+    expect(
+      D(hostBalanceAfter.value.uiAmount - hostBalanceBefore.value.uiAmount)
+    ).to.equal(D_BET_SIZE * 4);
+
+    const { games } = await program.account.games.fetch(gamesPda);
+    gameIndices.forEach((index) => expect(games[index].isClosed).to.be.true);
+
+    const opponentProfile = await program.account.playerProfile.fetch(
+      profilePda(mockPlayerBKeypair.publicKey)
+    );
+    expect(opponentProfile.losses).to.equal(lossesBefore + 2);
+  });
+
+  describe("intent matching", () => {
+    const [intentQueuePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("intents"), marketSeed(MARKET)],
+      program.programId
+    );
+
+    const intent = (prediction: boolean) => ({
+      market: MARKET,
+      prediction,
+      tier: 0,
+      betSize: new BN(D_BET_SIZE),
+    });
+
+    const submitIntent = (
+      prediction: boolean,
+      player: anchor.web3.Keypair,
+      playerTokenAccount: PublicKey
+    ) =>
+      program.methods
+        .submitIntent(intent(prediction))
+        .accounts({
+          player: player.publicKey,
+          playerTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([player])
+        .rpc({ commitment: "confirmed" });
+
+    it("refunds a cancelled intent", async () => {
+      await credit(playerATokenAccount, D_BET_SIZE);
+      const balanceBefore = await connection.getTokenAccountBalance(
+        playerATokenAccount
+      );
+
+      await submitIntent(true, mockPlayerAKeypair, playerATokenAccount);
+
+      const queue = await program.account.intentQueue.fetch(intentQueuePda);
+      const [queued] = queue.intents;
+      expect(queued.player.toString()).to.equal(
+        mockPlayerAKeypair.publicKey.toString()
+      );
+      expect(queued.amount.toNumber()).to.equal(D_BET_SIZE);
+
+      await program.methods
+        .cancelIntent(MARKET, queued.id)
+        .accounts({
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const balanceAfter = await connection.getTokenAccountBalance(
+        playerATokenAccount
+      );
+      expect(balanceAfter.value.amount).to.equal(balanceBefore.value.amount);
+
+      const { intents } = await program.account.intentQueue.fetch(
+        intentQueuePda
+      );
+      expect(intents.length).to.equal(0);
+    });
+
+    it("fails to match an empty queue", async () => {
+      try {
+        await program.methods
+          .matchIntents(MARKET, 1)
+          .accounts({ signer: SIGNER.publicKey })
+          .signers([SIGNER])
+          .rpc();
+
+        assert.fail("Should not match without opposite intents");
+      } catch (error) {
+        expect(error.message).to.include("NoMatchingIntents");
+      }
+    });
+
+    it("matches opposite intents into a game", async () => {
+      await credit(playerATokenAccount, D_BET_SIZE);
+      await credit(playerBTokenAccount, D_BET_SIZE);
+
+      await submitIntent(true, mockPlayerAKeypair, playerATokenAccount);
+      await submitIntent(false, mockPlayerBKeypair, playerBTokenAccount);
+
+      const queue = await program.account.intentQueue.fetch(intentQueuePda);
+      const submitIndex = queue.intents[queue.intents.length - 1].priceIndex;
+
+      // The game starts from the latest price at crank time, still within the join threshold
+      const priceAccount = await program.account.prices.fetch(pricesPda);
+      await program.methods
+        .addPrice(MARKET, priceAccount.prices[submitIndex])
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+      const startIndex = submitIndex + 1;
+
+      // Wallets and profiles of the matched players receive back their anti-spam deposits
+      const remainingAccounts = [mockPlayerAKeypair, mockPlayerBKeypair].flatMap(
+        ({ publicKey }) => [
+          { pubkey: publicKey, isWritable: true, isSigner: false },
+          { pubkey: profilePda(publicKey), isWritable: true, isSigner: false },
+        ]
+      );
+
+      await program.methods
+        .matchIntents(MARKET, 1)
+        .accounts({ signer: SIGNER.publicKey })
+        .remainingAccounts(remainingAccounts)
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      const { games } = await program.account.games.fetch(gamesPda);
+      const game = games[games.length - 1];
+      expect(game.host.toString()).to.equal(
+        mockPlayerAKeypair.publicKey.toString()
+      );
+      expect(game.opponent.toString()).to.equal(
+        mockPlayerBKeypair.publicKey.toString()
+      );
+      expect(game.hostPrediction).to.equal(true);
+      expect(game.amount.toNumber()).to.equal(D_BET_SIZE);
+      expect(game.opponentAmount.toNumber()).to.equal(D_BET_SIZE);
+      expect(game.priceIndex).to.equal(startIndex);
+
+      const { intents } = await program.account.intentQueue.fetch(
+        intentQueuePda
+      );
+      expect(intents.length).to.equal(0);
+    });
+  });
+
+  it("quick-joins the oldest compatible open game", async () => {
+    const QUICK_MARKET = 2;
+    await program.methods
+      .initializePrices({
+        initialPrice: new BN(D(1500, PRICE_DECIMALS)),
+        priceDecimals: PRICE_DECIMALS,
+        market: QUICK_MARKET,
+      })
+      .accounts({ signer: SIGNER.publicKey })
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    await credit(playerATokenAccount, D_BET_SIZE);
+    await credit(playerBTokenAccount, D_BET_SIZE);
+
+    await program.methods
+      .createGame({ ...directional(true), market: QUICK_MARKET })
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+
+    const quickJoin = (prediction: boolean, maxStake: number) =>
+      program.methods
+        .quickJoin({
+          market: QUICK_MARKET,
+          prediction,
+          maxStake: new BN(maxStake),
+        })
+        .accounts({
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair]);
+
+    for (const [prediction, maxStake] of [
+      [true, D_BET_SIZE],
+      [false, D_BET_SIZE - 1],
+    ] as [boolean, number][]) {
+      try {
+        await quickJoin(prediction, maxStake).rpc();
+
+        assert.fail("Should not join an incompatible game");
+      } catch (error) {
+        expect(error.message).to.include("NoCompatibleGame");
+      }
+    }
+
+    await quickJoin(false, D_BET_SIZE).rpc({ commitment: "confirmed" });
+
+    const { games } = await program.account.games.fetch(gamesPda);
+    expect(games[gameIndex].opponent.toString()).to.equal(
+      mockPlayerBKeypair.publicKey.toString()
+    );
+
+    const [openGamesPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("open_games"), marketSeed(QUICK_MARKET)],
+      program.programId
+    );
+    const openGames = await program.account.openGames.fetch(openGamesPda);
+    expect(openGames.gameIndices).to.not.include(gameIndex);
+  });
+
+  it("splits a partial fill game between several fillers", async () => {
+    const signerTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        connection,
+        SIGNER,
+        mint,
+        SIGNER.publicKey
+      )
+    ).address;
+
+    await credit(playerATokenAccount, D_BET_SIZE);
+    await credit(playerBTokenAccount, D_BET_SIZE * 0.3);
+    await credit(signerTokenAccount, D_BET_SIZE * 0.2);
+
+    await program.methods
+      .createGame({ ...directional(true), partialFills: true })
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+
+    try {
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc();
+
+      assert.fail("Should not join a partial fill game");
+    } catch (error) {
+      expect(error.message).to.include("PartialFillsOnly");
+    }
+
+    const fillers: [anchor.web3.Keypair, PublicKey, number][] = [
+      [mockPlayerBKeypair, playerBTokenAccount, D_BET_SIZE * 0.3],
+      [SIGNER, signerTokenAccount, D_BET_SIZE * 0.2],
+    ];
+
+    for (const [filler, tokenAccount, amount] of fillers) {
+      await program.methods
+        .fillGame(gameIndex, new BN(amount))
+        .accounts({
+          prices: pricesPda,
+          player: filler.publicKey,
+          playerTokenAccount: tokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([filler])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    const game = (await program.account.games.fetch(gamesPda)).games[
+      gameIndex
+    ];
+    expect(game.filledAmount.toNumber()).to.equal(D_BET_SIZE * 0.5);
+
+    // Price drops 6%, the fillers backing DOWN win
+    const priceAccount = await program.account.prices.fetch(pricesPda);
+    const gamePrice = priceAccount.prices[game.priceIndex];
+    await program.methods
+      .addPrice(MARKET, gamePrice.mul(new BN(94)).div(new BN(100)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    for (const [filler, tokenAccount, amount] of fillers) {
+      const balanceBefore = await connection.getTokenAccountBalance(
+        tokenAccount
+      );
+
+      await program.methods
+        .claimFill(gameIndex)
+        .accounts({
+          opponentProfile: profilePda(mockPlayerAKeypair.publicKey),
+          player: filler.publicKey,
+          playerTokenAccount: tokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          pricesAccount: pricesPda,
+        })
+        .signers([filler])
+        .rpc({ commitment: "confirmed" });
+
+      const balanceAfter = await connection.getTokenAccountBalance(
+        tokenAccount
+      );
+      expect(
+        Number(balanceAfter.value.amount) - Number(balanceBefore.value.amount)
+      ).to.equal(amount * 2);
+    }
+
+    // The host gets back the half of the stake nobody filled
+    const hostBalanceBefore = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+
+    await program.methods
+      .withdrawFromGame(gameIndex)
+      .accounts({
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const hostBalanceAfter = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+    expect(
+      Number(hostBalanceAfter.value.amount) -
+        Number(hostBalanceBefore.value.amount)
+    ).to.equal(D_BET_SIZE * 0.5);
+  });
+
+  it("records the losses of fillers when the host wins", async () => {
+    await credit(playerATokenAccount, D_BET_SIZE);
+    await credit(playerBTokenAccount, D_BET_SIZE * 0.5);
+
+    await program.methods
+      .createGame({ ...directional(true), partialFills: true })
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gameIndex =
+      (await program.account.games.fetch(gamesPda)).games.length - 1;
+
+    await program.methods
+      .fillGame(gameIndex, new BN(D_BET_SIZE * 0.5))
+      .accounts({
+        prices: pricesPda,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    // Price rises 6%, the host backing UP wins
+    const game = (await program.account.games.fetch(gamesPda)).games[
+      gameIndex
+    ];
+    const priceAccount = await program.account.prices.fetch(pricesPda);
+    const gamePrice = priceAccount.prices[game.priceIndex];
+    await program.methods
+      .addPrice(MARKET, gamePrice.mul(new BN(106)).div(new BN(100)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    const settleFill = () =>
+      program.methods
+        .settleFill(gameIndex, mockPlayerBKeypair.publicKey)
+        .accounts({
+          wagerMint: wagerMintPda,
+          hostProfile: profilePda(mockPlayerAKeypair.publicKey),
+        });
+
+    try {
+      await settleFill().rpc();
+
+      assert.fail("Should not settle a fill before the host's claim");
+    } catch (error) {
+      expect(error.message).to.include("GameNotFinished");
+    }
+
+    await program.methods
+      .claimWinnings(gameIndex)
+      .accounts({
+        pricesAccount: pricesPda,
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const fillerBefore = await program.account.playerProfile.fetch(
+      profilePda(mockPlayerBKeypair.publicKey)
+    );
+    await settleFill().rpc({ commitment: "confirmed" });
+
+    const fillerAfter = await program.account.playerProfile.fetch(
+      profilePda(mockPlayerBKeypair.publicKey)
+    );
+    expect(fillerAfter.losses).to.equal(fillerBefore.losses + 1);
+    expect(fillerAfter.currentStreak).to.equal(0);
+    expect(fillerAfter.rating).to.be.below(fillerBefore.rating);
+
+    try {
+      await settleFill().rpc();
+
+      assert.fail("Should not settle a fill twice");
+    } catch (error) {
+      expect(error.message).to.include("FillNotFound");
+    }
+  });
+
+  it("plays games from the in-program balance", async () => {
+    const [balancePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("balance"),
+        mockPlayerAKeypair.publicKey.toBuffer(),
+        mint.toBuffer(),
+      ],
+      program.programId
+    );
+
+    await credit(playerATokenAccount, D_BET_SIZE * 2);
+    const walletBalanceBefore = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+
+    await program.methods
+      .deposit(new BN(D_BET_SIZE * 2))
+      .accounts({
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    let balance = await program.account.balance.fetch(balancePda);
+    expect(balance.amount.toNumber()).to.equal(D_BET_SIZE * 2);
+
+    // No token accounts needed, the stake comes out of the balance
+    await program.methods
+      .createGame(directional(true))
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        mint,
+        balance: balancePda,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    balance = await program.account.balance.fetch(balancePda);
+    expect(balance.amount.toNumber()).to.equal(D_BET_SIZE);
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+
+    await program.methods
+      .withdrawFromGame(gameIndex)
+      .accounts({
+        player: mockPlayerAKeypair.publicKey,
+        mint,
+        balance: balancePda,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    balance = await program.account.balance.fetch(balancePda);
+    expect(balance.amount.toNumber()).to.equal(D_BET_SIZE * 2);
+
+    await program.methods
+      .withdraw(new BN(D_BET_SIZE * 2))
+      .accounts({
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    balance = await program.account.balance.fetch(balancePda);
+    expect(balance.amount.toNumber()).to.equal(0);
+
+    const walletBalanceAfter = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+    expect(walletBalanceAfter.value.amount).to.equal(
+      walletBalanceBefore.value.amount
+    );
+
+    try {
+      await program.methods
+        .withdraw(new BN(1))
+        .accounts({
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc();
+
+      assert.fail("Should not withdraw more than the balance");
+    } catch (error) {
+      expect(error.message).to.include("InsufficientBalance");
+    }
+  });
+
+  it("cancels a joined game once both players agree", async () => {
+    await credit(playerATokenAccount, D_BET_SIZE);
+    await credit(playerBTokenAccount, D_BET_SIZE);
+
+    await program.methods
+      .createGame(directional(true))
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+
+    await program.methods
+      .joinGame(gameIndex)
+      .accounts({
+        prices: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const hostBalanceBefore = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+    const opponentBalanceBefore = await connection.getTokenAccountBalance(
+      playerBTokenAccount
+    );
+
+    const cancel = (player: Keypair, counterparty: Keypair) =>
+      program.methods
+        .cancelGame(gameIndex)
+        .accounts({
+          player: player.publicKey,
+          counterparty: counterparty.publicKey,
+          playerTokenAccount:
+            player === mockPlayerAKeypair
+              ? playerATokenAccount
+              : playerBTokenAccount,
+          counterpartyTokenAccount:
+            counterparty === mockPlayerAKeypair
+              ? playerATokenAccount
+              : playerBTokenAccount,
+          mint,
+          prices: pricesPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([player])
+        .rpc({ commitment: "confirmed" });
+    const withdrawCancel = (player: Keypair) =>
+      program.methods
+        .withdrawCancel(gameIndex)
+        .accounts({ player: player.publicKey })
+        .signers([player])
+        .rpc({ commitment: "confirmed" });
+
+    await cancel(mockPlayerAKeypair, mockPlayerBKeypair);
+
+    let game = (await program.account.games.fetch(gamesPda)).games[gameIndex];
+    expect(game.cancelProposer.toBase58()).to.equal(
+      mockPlayerAKeypair.publicKey.toBase58()
+    );
+    expect(game.isClosed).to.equal(false);
+
+    try {
+      await withdrawCancel(mockPlayerBKeypair);
+
+      assert.fail("Should only let the proposer withdraw");
+    } catch (error) {
+      expect(error.message).to.include("NoCancelProposal");
+    }
+
+    await withdrawCancel(mockPlayerAKeypair);
+    game = (await program.account.games.fetch(gamesPda)).games[gameIndex];
+    expect(game.cancelProposer).to.equal(null);
+
+    // After the withdrawal the other side only proposes anew
+    await cancel(mockPlayerBKeypair, mockPlayerAKeypair);
+    game = (await program.account.games.fetch(gamesPda)).games[gameIndex];
+    expect(game.isClosed).to.equal(false);
+
+    await cancel(mockPlayerAKeypair, mockPlayerBKeypair);
+
+    game = (await program.account.games.fetch(gamesPda)).games[gameIndex];
+    expect(game.isCancelled).to.equal(true);
+    expect(game.isClosed).to.equal(true);
+
+    const hostBalanceAfter = await connection.getTokenAccountBalance(
+      playerATokenAccount
+    );
+    const opponentBalanceAfter = await connection.getTokenAccountBalance(
+      playerBTokenAccount
+    );
+    expect(
+      Number(hostBalanceAfter.value.amount) -
+        Number(hostBalanceBefore.value.amount)
+    ).to.equal(game.amount.toNumber());
+    expect(
+      Number(opponentBalanceAfter.value.amount) -
+        Number(opponentBalanceBefore.value.amount)
+    ).to.equal(game.opponentAmount.toNumber());
+
+    try {
+      await cancel(mockPlayerAKeypair, mockPlayerBKeypair);
+
+      assert.fail("Should not cancel a cancelled game");
+    } catch (error) {
+      expect(error.message).to.include("GameAlreadyClosed");
+    }
+  });
+
+  it("reserves a rematch for the previous opponent", async () => {
+    await credit(playerATokenAccount, D_BET_SIZE * 2);
+    await credit(playerBTokenAccount, D_BET_SIZE * 2);
+
+    await program.methods
+      .createGame(directional(true))
+      .accounts({
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+
+    await program.methods
+      .joinGame(gameIndex)
+      .accounts({
+        prices: pricesPda,
+        authority: mockPlayerBKeypair.publicKey,
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    try {
+      await program.methods
+        .rematch(gameIndex, false)
+        .accounts({
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          pricesAccount: pricesPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc();
+
+      assert.fail("Should not rematch a game before it is settled");
+    } catch (error) {
+      expect(error.message).to.include("RematchNotAllowed");
+    }
+
+    const priceAccount = await program.account.prices.fetch(pricesPda);
+    const gamePrice =
+      priceAccount.prices[gamesAccount.games[gameIndex].priceIndex];
+    await program.methods
+      .addPrice(MARKET, gamePrice.mul(new BN(106)).div(new BN(100)))
+      .signers([SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    await program.methods
+      .claimWinnings(gameIndex)
+      .accounts({
+        opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+        pricesAccount: pricesPda,
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    // The losing opponent asks for another round on the same side
+    await program.methods
+      .rematch(gameIndex, false)
+      .accounts({
+        player: mockPlayerBKeypair.publicKey,
+        playerTokenAccount: playerBTokenAccount,
+        mint,
+        pricesAccount: pricesPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerBKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const { games } = await program.account.games.fetch(gamesPda);
+    const rematchIndex = games.length - 1;
+    const rematch = games[rematchIndex];
+    expect(rematch.host.toBase58()).to.equal(
+      mockPlayerBKeypair.publicKey.toBase58()
+    );
+    expect(rematch.hostPrediction).to.equal(false);
+    expect(rematch.reservedFor.toBase58()).to.equal(
+      mockPlayerAKeypair.publicKey.toBase58()
+    );
+    expect(rematch.priceIndex).to.equal(
+      (await program.account.prices.fetch(pricesPda)).prices.length - 1
+    );
+
+    // Quick join finds the rematch too, once the reservation ends
+    const [openGamesPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("open_games"), marketSeed(MARKET)],
+      program.programId
+    );
+    const openGames = await program.account.openGames.fetch(openGamesPda);
+    expect(openGames.gameIndices).to.include(rematchIndex);
+
+    await program.methods
+      .joinGame(rematchIndex)
+      .accounts({
+        prices: pricesPda,
+        authority: mockPlayerAKeypair.publicKey,
+        player: mockPlayerAKeypair.publicKey,
+        playerTokenAccount: playerATokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([mockPlayerAKeypair])
+      .rpc({ commitment: "confirmed" });
+
+    const finalGames = await program.account.games.fetch(gamesPda);
+    expect(finalGames.games[rematchIndex].opponent.toBase58()).to.equal(
+      mockPlayerAKeypair.publicKey.toBase58()
+    );
+    expect(finalGames.games[gameIndex].isRematched).to.be.true;
+
+    try {
+      await program.methods
+        .rematch(gameIndex, true)
+        .accounts({
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          pricesAccount: pricesPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc();
+
+      assert.fail("Should not rematch a game twice");
+    } catch (error) {
+      expect(error.message).to.include("RematchNotAllowed");
+    }
+  });
+
+  it("lets a sponsor pay the rent of a player without SOL", async () => {
+    const newPlayerKeypair = Keypair.generate();
+    const newPlayer = newPlayerKeypair.publicKey;
+    const { address: newPlayerTokenAccount } =
+      await getOrCreateAssociatedTokenAccount(
+        connection,
+        SIGNER,
+        mint,
+        newPlayer
+      );
+    await credit(newPlayerTokenAccount, D_BET_SIZE);
+
+    await program.methods
+      .createGame(directional(true))
+      .accounts({
+        authority: newPlayer,
+        player: newPlayer,
+        payer: SIGNER.publicKey,
+        playerTokenAccount: newPlayerTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([newPlayerKeypair, SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    const gamesAccount = await program.account.games.fetch(gamesPda);
+    const gameIndex = gamesAccount.games.length - 1;
+    expect(gamesAccount.games[gameIndex].host.toBase58()).to.equal(
+      newPlayer.toBase58()
+    );
+    expect(await connection.getBalance(newPlayer)).to.equal(0);
+
+    await program.methods
+      .withdrawFromGame(gameIndex)
+      .accounts({
+        player: newPlayer,
+        payer: SIGNER.publicKey,
+        playerTokenAccount: newPlayerTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([newPlayerKeypair, SIGNER])
+      .rpc({ commitment: "confirmed" });
+
+    const walletBalance = await connection.getTokenAccountBalance(
+      newPlayerTokenAccount
+    );
+    expect(walletBalance.value.amount).to.equal(D_BET_SIZE.toString());
+  });
+
+  it("reconciles the vault against locked stakes", async () => {
+    const report = await program.methods
+      .reconcileVault()
+      .accounts({ mint })
+      .view();
+
+    const vaultBalance = await connection.getTokenAccountBalance(vaultPda);
+    const { lockedAmount } = await program.account.wagerMint.fetch(
+      wagerMintPda
+    );
+    expect(report.balance.toString()).to.equal(vaultBalance.value.amount);
+    expect(report.lockedAmount.toString()).to.equal(lockedAmount.toString());
+    expect(report.surplus.toNumber()).to.equal(0);
+    expect(report.deficit.toNumber()).to.equal(0);
+
+    // Tokens sent to the vault outside of games show up as surplus
+    await credit(vaultPda, D(5));
+
+    const reportAfter = await program.methods
+      .reconcileVault()
+      .accounts({ mint })
+      .view();
+    expect(reportAfter.surplus.toNumber()).to.equal(D(5));
+    expect(reportAfter.deficit.toNumber()).to.equal(0);
+  });
+
+  describe("seasons", () => {
+    const SEASON_ID = 1;
+    const FEE_BPS = 500;
+
+    const [seasonPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("season"), new BN(SEASON_ID).toArrayLike(Buffer, "le", 4)],
+      program.programId
+    );
+    const seasonScorePdaOf = (player: PublicKey) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("season_score"),
+          new BN(SEASON_ID).toArrayLike(Buffer, "le", 4),
+          player.toBuffer(),
+        ],
+        program.programId
+      )[0];
+    const seasonScorePda = seasonScorePdaOf(mockPlayerAKeypair.publicKey);
+    const opponentSeasonScorePda = seasonScorePdaOf(
+      mockPlayerBKeypair.publicKey
+    );
+    let endTime: number;
+    // Fees kept before the season roll into its prize pool
+    let carriedFees: number;
+
+    before(async () => {
+      await program.methods
+        .setProtocolFee(FEE_BPS, 0)
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc();
+
+      const now = Math.floor(Date.now() / 1000);
+      endTime = now + 10;
+      await program.methods
+        .createSeason(SEASON_ID, new BN(now - 60), new BN(endTime))
+        .accounts({ admin: SIGNER.publicKey, mint })
+        .signers([SIGNER])
+        .rpc();
+
+      carriedFees = (
+        await program.account.season.fetch(seasonPda)
+      ).prizePool.toNumber();
+
+      // Anyone can enter a player, the loser's score is created by the claim if missing
+      await program.methods
+        .enterSeason(SEASON_ID)
+        .accounts({ player: mockPlayerAKeypair.publicKey })
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setProtocolFee(0, 0)
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc();
+    });
+
+    it("rejects a protocol fee above the maximum", async () => {
+      try {
+        await program.methods
+          .setProtocolFee(1_001, 0)
+          .accounts({ admin: SIGNER.publicKey })
+          .signers([SIGNER])
+          .rpc();
+
+        assert.fail("Should not accept a fee above 10%");
+      } catch (error) {
+        expect(error.message).to.include("InvalidFee");
+      }
+    });
+
+    it("ranks the winner and pays the fee pool as prize", async () => {
+      await credit(playerATokenAccount, D_BET_SIZE);
+      await credit(playerBTokenAccount, D_BET_SIZE);
+
+      await program.methods
+        .createGame(directional(true))
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gamesAccount = await program.account.games.fetch(gamesPda);
+      const gameIndex = gamesAccount.games.length - 1;
+
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const priceAccount = await program.account.prices.fetch(pricesPda);
+      const gamePrice =
+        priceAccount.prices[gamesAccount.games[gameIndex].priceIndex];
+      await program.methods
+        .addPrice(MARKET, gamePrice.mul(new BN(106)).div(new BN(100)))
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      const hostBalanceBefore = await connection.getTokenAccountBalance(
+        playerATokenAccount
+      );
+
+      const claim = (opponentSeasonScore: PublicKey | null) =>
+        program.methods
+          .claimWinnings(gameIndex)
+          .accounts({
+            opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+            pricesAccount: pricesPda,
+            authority: mockPlayerAKeypair.publicKey,
+            player: mockPlayerAKeypair.publicKey,
+            playerTokenAccount: playerATokenAccount,
+            mint,
+            season: seasonPda,
+            seasonScore: seasonScorePda,
+            opponentSeasonScore,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([mockPlayerAKeypair]);
+
+      try {
+        await claim(null).rpc();
+
+        assert.fail("Should not settle without the loser's season score");
+      } catch (error) {
+        expect(error.message).to.include("MissingSeasonAccounts");
+      }
+
+      await claim(opponentSeasonScorePda).rpc({ commitment: "confirmed" });
+
+      // The fee is kept from the pot of both stakes
+      const fee = (2 * D_BET_SIZE * FEE_BPS) / 10_000;
+      const hostBalanceAfter = await connection.getTokenAccountBalance(
+        playerATokenAccount
+      );
+      expect(
+        Number(hostBalanceAfter.value.amount) -
+          Number(hostBalanceBefore.value.amount)
+      ).to.equal(2 * D_BET_SIZE - fee);
+
+      const season = await program.account.season.fetch(seasonPda);
+      expect(season.leaderboard.length).to.equal(1);
+      expect(season.leaderboard[0].player.toBase58()).to.equal(
+        mockPlayerAKeypair.publicKey.toBase58()
+      );
+      expect(season.leaderboard[0].netWinnings.toNumber()).to.equal(
+        D_BET_SIZE - fee
+      );
+      expect(season.prizePool.toNumber()).to.equal(carriedFees + fee);
+
+      // The loss is floored at zero and keeps the loser off the leaderboard
+      const opponentScore = await program.account.seasonScore.fetch(
+        opponentSeasonScorePda
+      );
+      expect(opponentScore.netWinnings.toNumber()).to.equal(0);
+
+      try {
+        await program.methods
+          .closeSeason(SEASON_ID)
+          .accounts({ admin: SIGNER.publicKey })
+          .signers([SIGNER])
+          .rpc();
+
+        assert.fail("Should not close a running season");
+      } catch (error) {
+        expect(error.message).to.include("SeasonNotEnded");
+      }
+
+      await new Promise((resolve) =>
+        setTimeout(resolve, (endTime + 2) * 1000 - Date.now())
+      );
+
+      await program.methods
+        .closeSeason(SEASON_ID)
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      const closedSeason = await program.account.season.fetch(seasonPda);
+      expect(closedSeason.isClosed).to.be.true;
+      expect(closedSeason.prizePool.toNumber()).to.equal(carriedFees + fee);
+
+      await program.methods
+        .claimSeasonPrize(SEASON_ID)
+        .accounts({
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const hostBalanceFinal = await connection.getTokenAccountBalance(
+        playerATokenAccount
+      );
+      expect(
+        Number(hostBalanceFinal.value.amount) -
+          Number(hostBalanceAfter.value.amount)
+      ).to.equal(carriedFees + fee);
+    });
+  });
+
+  describe("referrals", () => {
+    const FEE_BPS = 500;
+    const REFERRAL_SHARE_BPS = 2_000;
+    const referrer = SIGNER.publicKey;
+
+    const [referralRewardsPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("referral"), referrer.toBuffer(), mint.toBuffer()],
+      program.programId
+    );
+
+    before(async () => {
+      await program.methods
+        .setProtocolFee(FEE_BPS, REFERRAL_SHARE_BPS)
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setProtocolFee(0, 0)
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc();
+    });
+
+    it("registers a referrer only once", async () => {
+      await program.methods
+        .registerReferrer(referrer)
+        .accounts({ player: mockPlayerBKeypair.publicKey, mint })
+        .signers([mockPlayerBKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const profile = await program.account.playerProfile.fetch(
+        profilePda(mockPlayerBKeypair.publicKey)
+      );
+      expect(profile.referrer.toBase58()).to.equal(referrer.toBase58());
+
+      try {
+        await program.methods
+          .registerReferrer(mockPlayerAKeypair.publicKey)
+          .accounts({ player: mockPlayerBKeypair.publicKey, mint })
+          .signers([mockPlayerBKeypair])
+          .rpc();
+
+        assert.fail("Should not replace the referrer");
+      } catch (error) {
+        expect(error.message).to.include("ReferrerAlreadySet");
+      }
+    });
+
+    it("credits the referrer of the loser with a share of the fee", async () => {
+      await credit(playerATokenAccount, D_BET_SIZE);
+      await credit(playerBTokenAccount, D_BET_SIZE);
+
+      await program.methods
+        .createGame(directional(true))
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gamesAccount = await program.account.games.fetch(gamesPda);
+      const gameIndex = gamesAccount.games.length - 1;
+
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const priceAccount = await program.account.prices.fetch(pricesPda);
+      const gamePrice =
+        priceAccount.prices[gamesAccount.games[gameIndex].priceIndex];
+      await program.methods
+        .addPrice(MARKET, gamePrice.mul(new BN(106)).div(new BN(100)))
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      try {
+        await program.methods
+          .claimWinnings(gameIndex)
+          .accounts({
+            pricesAccount: pricesPda,
+            authority: mockPlayerAKeypair.publicKey,
+            player: mockPlayerAKeypair.publicKey,
+            playerTokenAccount: playerATokenAccount,
+            mint,
+            opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([mockPlayerAKeypair])
+          .rpc();
+
+        assert.fail("Should require the rewards of the loser's referrer");
+      } catch (error) {
+        expect(error.message).to.include("InvalidReferralRewards");
+      }
+
+      await program.methods
+        .claimWinnings(gameIndex)
+        .accounts({
+          pricesAccount: pricesPda,
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+          opponentReferralRewards: referralRewardsPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const fee = (2 * D_BET_SIZE * FEE_BPS) / 10_000;
+      const share = (fee * REFERRAL_SHARE_BPS) / 10_000;
+      const rewards = await program.account.referralRewards.fetch(
+        referralRewardsPda
+      );
+      expect(rewards.amount.toNumber()).to.equal(share);
+
+      const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        SIGNER,
+        mint,
+        referrer
+      );
+      const referrerBalanceBefore = await connection.getTokenAccountBalance(
+        referrerTokenAccount.address
+      );
+
+      await program.methods
+        .claimReferralRewards()
+        .accounts({
+          player: referrer,
+          playerTokenAccount: referrerTokenAccount.address,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      const referrerBalanceAfter = await connection.getTokenAccountBalance(
+        referrerTokenAccount.address
+      );
+      expect(
+        Number(referrerBalanceAfter.value.amount) -
+          Number(referrerBalanceBefore.value.amount)
+      ).to.equal(share);
+    });
+  });
+
+  describe("anti-spam limits", () => {
+    const SPAM_DEPOSIT = LAMPORTS_PER_SOL / 100;
+
+    after(async () => {
+      await program.methods
+        .setSpamLimits(0, new BN(0))
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc();
+    });
+
+    it("limits open games per player and refunds the deposit on join", async () => {
+      const { games } = await program.account.games.fetch(gamesPda);
+      const openGames = games.filter(
+        (game) =>
+          game.host.equals(mockPlayerAKeypair.publicKey) &&
+          !game.isClosed &&
+          game.opponent === null &&
+          game.filledAmount.isZero()
+      ).length;
+
+      await program.methods
+        .setSpamLimits(openGames + 1, new BN(SPAM_DEPOSIT))
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc();
+
+      await credit(playerATokenAccount, 2 * D_BET_SIZE);
+      await credit(playerBTokenAccount, D_BET_SIZE);
+
+      await program.methods
+        .createGame(directional(true))
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gamesAccount = await program.account.games.fetch(gamesPda);
+      const gameIndex = gamesAccount.games.length - 1;
+      expect(gamesAccount.games[gameIndex].spamDeposit.toNumber()).to.equal(
+        SPAM_DEPOSIT
+      );
+
+      try {
+        await program.methods
+          .createGame(directional(true))
+          .accounts({
+            authority: mockPlayerAKeypair.publicKey,
+            player: mockPlayerAKeypair.publicKey,
+            playerTokenAccount: playerATokenAccount,
+            mint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([mockPlayerAKeypair])
+          .rpc();
+
+        assert.fail("Should not exceed the open games limit");
+      } catch (error) {
+        expect(error.message).to.include("PlayerOpenGamesLimit");
+      }
+
+      const hostLamportsBefore = await connection.getBalance(
+        mockPlayerAKeypair.publicKey
+      );
+
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          host: mockPlayerAKeypair.publicKey,
+          hostProfile: profilePda(mockPlayerAKeypair.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const hostLamportsAfter = await connection.getBalance(
+        mockPlayerAKeypair.publicKey
+      );
+      expect(hostLamportsAfter - hostLamportsBefore).to.equal(SPAM_DEPOSIT);
+
+      const finalGames = await program.account.games.fetch(gamesPda);
+      expect(finalGames.games[gameIndex].spamDeposit.toNumber()).to.equal(0);
+    });
+  });
+
+  describe("gated markets", () => {
+    const GATED_MARKET = 3;
+
+    const [gatedPricesPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("prices"), marketSeed(GATED_MARKET)],
+      program.programId
+    );
+    const [marketAccessPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("market_access"), marketSeed(GATED_MARKET)],
+      program.programId
+    );
+    const allowlistPda = (player: PublicKey) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("allowlist"),
+          marketSeed(GATED_MARKET),
+          player.toBuffer(),
+        ],
+        program.programId
+      )[0];
+
+    before(async () => {
+      await program.methods
+        .initializePrices({
+          initialPrice: new BN(D(1500, PRICE_DECIMALS)),
+          priceDecimals: PRICE_DECIMALS,
+          market: GATED_MARKET,
+        })
+        .accounts({ signer: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      await program.methods
+        .setMarketAccess(GATED_MARKET, null)
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      await program.methods
+        .addToAllowlist(GATED_MARKET, mockPlayerAKeypair.publicKey)
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    it("rejects players outside the allowlist", async () => {
+      await credit(playerBTokenAccount, D_BET_SIZE);
+
+      try {
+        await program.methods
+          .createGame({ ...directional(true), market: GATED_MARKET })
+          .accounts({
+            authority: mockPlayerBKeypair.publicKey,
+            player: mockPlayerBKeypair.publicKey,
+            playerTokenAccount: playerBTokenAccount,
+            mint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([mockPlayerBKeypair])
+          .rpc();
+
+        assert.fail("Should not create games in a gated market");
+      } catch (error) {
+        expect(error.message).to.include("MarketAccessDenied");
+      }
+    });
+
+    it("lets allowlisted players and gate mint holders play", async () => {
+      await credit(playerATokenAccount, D_BET_SIZE);
+      await credit(playerBTokenAccount, D_BET_SIZE);
+
+      await program.methods
+        .createGame({ ...directional(true), market: GATED_MARKET })
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          allowlistEntry: allowlistPda(mockPlayerAKeypair.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gamesAccount = await program.account.games.fetch(gamesPda);
+      const gameIndex = gamesAccount.games.length - 1;
+      expect(gamesAccount.games[gameIndex].isGated).to.be.true;
+
+      try {
+        await program.methods
+          .joinGame(gameIndex)
+          .accounts({
+            prices: gatedPricesPda,
+            authority: mockPlayerBKeypair.publicKey,
+            player: mockPlayerBKeypair.publicKey,
+            playerTokenAccount: playerBTokenAccount,
+            mint,
+            marketAccess: marketAccessPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([mockPlayerBKeypair])
+          .rpc();
+
+        assert.fail("Should not join a gated game without access");
+      } catch (error) {
+        expect(error.message).to.include("MarketAccessDenied");
+      }
+
+      // Holders of the gate mint get in without being on the allowlist
+      await program.methods
+        .setMarketAccess(GATED_MARKET, mint)
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          prices: gatedPricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          marketAccess: marketAccessPda,
+          accessTokenAccount: playerBTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const finalGames = await program.account.games.fetch(gamesPda);
+      expect(finalGames.games[gameIndex].opponent.toBase58()).to.equal(
+        mockPlayerBKeypair.publicKey.toBase58()
+      );
+    });
+
+    it("opens running gated games once the gate is removed", async () => {
+      await credit(playerATokenAccount, D_BET_SIZE);
+      await credit(playerBTokenAccount, D_BET_SIZE);
+
+      await program.methods
+        .createGame({ ...directional(true), market: GATED_MARKET })
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          allowlistEntry: allowlistPda(mockPlayerAKeypair.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gameIndex =
+        (await program.account.games.fetch(gamesPda)).games.length - 1;
+
+      await program.methods
+        .removeMarketAccess(GATED_MARKET)
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      // No gate account to pass, the game is open to everyone
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          prices: gatedPricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const { games } = await program.account.games.fetch(gamesPda);
+      expect(games[gameIndex].isGated).to.be.false;
+      expect(games[gameIndex].opponent.toBase58()).to.equal(
+        mockPlayerBKeypair.publicKey.toBase58()
+      );
+    });
+  });
+
+  describe("session keys", () => {
+    const sessionKeypair = Keypair.generate();
+    const [sessionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("session"),
+        mockPlayerAKeypair.publicKey.toBuffer(),
+        sessionKeypair.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const [balancePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("balance"),
+        mockPlayerAKeypair.publicKey.toBuffer(),
+        mint.toBuffer(),
+      ],
+      program.programId
+    );
+
+    const sessionAccounts = {
+      authority: sessionKeypair.publicKey,
+      player: mockPlayerAKeypair.publicKey,
+      session: sessionPda,
+      mint,
+      balance: balancePda,
+    };
+
+    before(async () => {
+      await airdrop(sessionKeypair.publicKey, 1, connection);
+      await credit(playerATokenAccount, D_BET_SIZE * 2);
+
+      await program.methods
+        .deposit(new BN(D_BET_SIZE * 2))
+        .accounts({
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    it("lets a session key play from the balance up to its cap", async () => {
+      const expiresAt = Math.floor(Date.now() / 1000) + 3600;
+
+      await program.methods
+        .createSession(
+          sessionKeypair.publicKey,
+          new BN(expiresAt),
+          new BN(D_BET_SIZE)
+        )
+        .accounts({ player: mockPlayerAKeypair.publicKey })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      try {
+        await program.methods
+          .createGame(directional(true))
+          .accounts({
+            ...sessionAccounts,
+            balance: null,
+            playerTokenAccount: playerATokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([sessionKeypair])
+          .rpc();
+
+        assert.fail("Should not move wallet funds with a session key");
+      } catch (error) {
+        expect(error.message).to.include("SessionRequiresBalance");
+      }
+
+      await program.methods
+        .setSpamLimits(0, new BN(LAMPORTS_PER_SOL / 100))
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      try {
+        await program.methods
+          .createGame(directional(true))
+          .accounts(sessionAccounts)
+          .signers([sessionKeypair])
+          .rpc();
+
+        assert.fail("Should not put up the deposit with a session key");
+      } catch (error) {
+        expect(error.message).to.include("SessionSpamDeposit");
+      }
+
+      await program.methods
+        .setSpamLimits(0, new BN(0))
+        .accounts({ admin: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      await program.methods
+        .createGame(directional(true))
+        .accounts(sessionAccounts)
+        .signers([sessionKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gamesAccount = await program.account.games.fetch(gamesPda);
+      const gameIndex = gamesAccount.games.length - 1;
+      expect(gamesAccount.games[gameIndex].host.toBase58()).to.equal(
+        mockPlayerAKeypair.publicKey.toBase58()
+      );
+
+      const session = await program.account.session.fetch(sessionPda);
+      expect(session.spent.toNumber()).to.equal(D_BET_SIZE);
+
+      try {
+        await program.methods
+          .createGame(directional(true))
+          .accounts(sessionAccounts)
+          .signers([sessionKeypair])
+          .rpc();
+
+        assert.fail("Should not exceed the session spending cap");
+      } catch (error) {
+        expect(error.message).to.include("SessionCapExceeded");
+      }
+
+      await program.methods
+        .withdrawFromGame(gameIndex)
+        .accounts({
+          player: mockPlayerAKeypair.publicKey,
+          mint,
+          balance: balancePda,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    it("rejects a revoked session key", async () => {
+      await program.methods
+        .revokeSession(sessionKeypair.publicKey)
+        .accounts({ player: mockPlayerAKeypair.publicKey })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      try {
+        await program.methods
+          .createGame(directional(true))
+          .accounts({ ...sessionAccounts, session: null })
+          .signers([sessionKeypair])
+          .rpc();
+
+        assert.fail("Should not play with a revoked session key");
+      } catch (error) {
+        expect(error.message).to.include("InvalidSession");
+      }
+    });
+  });
+
+  describe("side bets", () => {
+    const hostBackerKeypair = Keypair.generate();
+    const opponentBackerKeypair = Keypair.generate();
+    let hostBackerTokenAccount: PublicKey;
+    let opponentBackerTokenAccount: PublicKey;
+
+    before(async () => {
+      ({ address: hostBackerTokenAccount } =
+        await getOrCreateAssociatedTokenAccount(
+          connection,
+          SIGNER,
+          mint,
+          hostBackerKeypair.publicKey
+        ));
+      ({ address: opponentBackerTokenAccount } =
+        await getOrCreateAssociatedTokenAccount(
+          connection,
+          SIGNER,
+          mint,
+          opponentBackerKeypair.publicKey
+        ));
+      await credit(hostBackerTokenAccount, D_BET_SIZE);
+      await credit(opponentBackerTokenAccount, D_BET_SIZE);
+    });
+
+    const backerAccounts = (
+      backer: PublicKey,
+      backerTokenAccount: PublicKey
+    ) => ({
+      backer,
+      backerTokenAccount,
+      mint,
+      prices: pricesPda,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+
+    it("pays the backers of the winner pro rata", async () => {
+      await credit(playerATokenAccount, D_BET_SIZE);
+      await credit(playerBTokenAccount, D_BET_SIZE);
+
+      await program.methods
+        .createGame(directional(true))
+        .accounts({
+          authority: mockPlayerAKeypair.publicKey,
+          player: mockPlayerAKeypair.publicKey,
+          playerTokenAccount: playerATokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gamesAccount = await program.account.games.fetch(gamesPda);
+      const gameIndex = gamesAccount.games.length - 1;
+
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          prices: pricesPda,
+          authority: mockPlayerBKeypair.publicKey,
+          player: mockPlayerBKeypair.publicKey,
+          playerTokenAccount: playerBTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      try {
+        await program.methods
+          .placeSideBet(gameIndex, true, new BN(D_BET_SIZE))
+          .accounts(
+            backerAccounts(mockPlayerAKeypair.publicKey, playerATokenAccount)
+          )
+          .signers([mockPlayerAKeypair])
+          .rpc();
+
+        assert.fail("Should not let a player back a side of their own game");
+      } catch (error) {
+        expect(error.message).to.include("PlayerCannotBackSide");
+      }
+
+      await program.methods
+        .placeSideBet(gameIndex, true, new BN(D_BET_SIZE))
+        .accounts(
+          backerAccounts(hostBackerKeypair.publicKey, hostBackerTokenAccount)
+        )
+        .signers([hostBackerKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      await program.methods
+        .placeSideBet(gameIndex, false, new BN(D_BET_SIZE))
+        .accounts(
+          backerAccounts(
+            opponentBackerKeypair.publicKey,
+            opponentBackerTokenAccount
+          )
+        )
+        .signers([opponentBackerKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const [sidePoolPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("side_pool"),
+          new BN(gameIndex).toArrayLike(Buffer, "le", 4),
+        ],
+        program.programId
+      );
+      let sidePool = await program.account.sidePool.fetch(sidePoolPda);
+      expect(sidePool.hostTotal.toNumber()).to.equal(D_BET_SIZE);
+      expect(sidePool.opponentTotal.toNumber()).to.equal(D_BET_SIZE);
+
+      const priceAccount = await program.account.prices.fetch(pricesPda);
+      const gamePrice =
+        priceAccount.prices[gamesAccount.games[gameIndex].priceIndex];
+      await program.methods
+        .addPrice(MARKET, gamePrice.mul(new BN(106)).div(new BN(100)))
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      // The game is decided, even though nobody claimed it yet
+      try {
+        await program.methods
+          .placeSideBet(gameIndex, false, new BN(D_BET_SIZE))
+          .accounts(
+            backerAccounts(
+              opponentBackerKeypair.publicKey,
+              opponentBackerTokenAccount
+            )
+          )
+          .signers([opponentBackerKeypair])
+          .rpc();
+
+        assert.fail("Should not take side bets on a decided game");
+      } catch (error) {
+        expect(error.message).to.include("SideBetsClosed");
+      }
+
+      const { feeBps } = await program.account.config.fetch(configPda);
+      const pool = D_BET_SIZE * 2;
+
+      await program.methods
+        .claimSideBet(gameIndex)
+        .accounts(
+          backerAccounts(hostBackerKeypair.publicKey, hostBackerTokenAccount)
+        )
+        .signers([hostBackerKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      sidePool = await program.account.sidePool.fetch(sidePoolPda);
+      expect(sidePool.hostWon).to.equal(true);
+
+      const hostBackerBalance = await connection.getTokenAccountBalance(
+        hostBackerTokenAccount
+      );
+      expect(Number(hostBackerBalance.value.amount)).to.equal(
+        pool - Math.floor((pool * feeBps) / 10_000)
+      );
+
+      await program.methods
+        .claimSideBet(gameIndex)
+        .accounts(
+          backerAccounts(
+            opponentBackerKeypair.publicKey,
+            opponentBackerTokenAccount
+          )
+        )
+        .signers([opponentBackerKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const opponentBackerBalance = await connection.getTokenAccountBalance(
+        opponentBackerTokenAccount
+      );
+      expect(Number(opponentBackerBalance.value.amount)).to.equal(0);
+    });
+  });
+
+  describe("native SOL wagers", () => {
+    const SOL_BET_SIZE = LAMPORTS_PER_SOL / 10;
+    const SOL_LIMITS = {
+      minBetSize: new BN(LAMPORTS_PER_SOL / 100),
+      maxBetSize: new BN(LAMPORTS_PER_SOL),
+    };
+    const [solEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("sol_escrow")],
+      program.programId
+    );
+
+    const solGame = (prediction: boolean) => ({
+      ...directional(prediction),
+      betSize: new BN(SOL_BET_SIZE),
+    });
+
+    // Token accounts are left out, stakes move through the SOL escrow
+    const solAccounts = (player: PublicKey) => ({
+      player,
+      mint: NATIVE_MINT,
+      playerTokenAccount: null,
+      vaultAccount: null,
+      solEscrow: solEscrowPda,
+      tokenProgram: null,
+    });
+
+    before(async () => {
+      await airdrop(mockPlayerAKeypair.publicKey, 2, connection);
+      await airdrop(mockPlayerBKeypair.publicKey, 2, connection);
+    });
+
+    it("registers native SOL under the native mint", async () => {
+      await program.methods
+        .initializeSolEscrow(SOL_LIMITS)
+        .accounts({ signer: SIGNER.publicKey })
+        .signers([SIGNER])
+        .rpc();
+
+      const [wagerMintPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("wager_mint"), NATIVE_MINT.toBuffer()],
+        program.programId
+      );
+      const wagerMint = await program.account.wagerMint.fetch(wagerMintPda);
+      expect(wagerMint.mint.toString()).to.equal(NATIVE_MINT.toString());
+      expect(wagerMint.isEnabled).to.equal(true);
+    });
+
+    it("prevents creating a token vault for the native mint", async () => {
+      try {
+        await program.methods
+          .initializeVault(WAGER_LIMITS)
+          .accounts({
+            signer: SIGNER.publicKey,
+            mint: NATIVE_MINT,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([SIGNER])
+          .rpc();
+
+        assert.fail("Should not create a token vault for native SOL");
+      } catch (error) {
+        expect(error.message).to.include("NativeMintVault");
+      }
+    });
+
+    it("refunds native SOL on withdrawal", async () => {
+      const escrowBalanceBefore = await connection.getBalance(solEscrowPda);
+
+      await program.methods
+        .createGame(solGame(true))
+        .accounts({
+          ...solAccounts(mockPlayerAKeypair.publicKey),
+          authority: mockPlayerAKeypair.publicKey,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gamesAccount = await program.account.games.fetch(gamesPda);
+      const gameIndex = gamesAccount.games.length - 1;
+      const game = gamesAccount.games[gameIndex];
+      expect(game.mint.toString()).to.equal(NATIVE_MINT.toString());
+      expect(game.amount.toNumber()).to.equal(SOL_BET_SIZE);
+      expect(await connection.getBalance(solEscrowPda)).to.equal(
+        escrowBalanceBefore + SOL_BET_SIZE
+      );
+
+      await program.methods
+        .withdrawFromGame(gameIndex)
+        .accounts(solAccounts(mockPlayerAKeypair.publicKey))
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      expect(await connection.getBalance(solEscrowPda)).to.equal(
+        escrowBalanceBefore
+      );
+    });
+
+    it("pays native SOL winnings to the winner", async () => {
+      const escrowBalanceBefore = await connection.getBalance(solEscrowPda);
+
+      await program.methods
+        .createGame(solGame(true))
+        .accounts({
+          ...solAccounts(mockPlayerAKeypair.publicKey),
+          authority: mockPlayerAKeypair.publicKey,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const gamesAccount = await program.account.games.fetch(gamesPda);
+      const gameIndex = gamesAccount.games.length - 1;
+
+      await program.methods
+        .joinGame(gameIndex)
+        .accounts({
+          ...solAccounts(mockPlayerBKeypair.publicKey),
+          authority: mockPlayerBKeypair.publicKey,
+          prices: pricesPda,
+        })
+        .signers([mockPlayerBKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      expect(await connection.getBalance(solEscrowPda)).to.equal(
+        escrowBalanceBefore + SOL_BET_SIZE * 2
+      );
+
+      const priceAccount = await program.account.prices.fetch(pricesPda);
+      const gamePrice =
+        priceAccount.prices[gamesAccount.games[gameIndex].priceIndex];
+      await program.methods
+        .addPrice(MARKET, gamePrice.mul(new BN(106)).div(new BN(100)))
+        .signers([SIGNER])
+        .rpc({ commitment: "confirmed" });
+
+      const hostBalanceBefore = await connection.getBalance(
+        mockPlayerAKeypair.publicKey
+      );
+
+      // The loser was referred, so their referrer's rewards are opened in the native mint too
+      const [solReferralRewardsPda] =
+        anchor.web3.PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("referral"),
+            SIGNER.publicKey.toBuffer(),
+            NATIVE_MINT.toBuffer(),
+          ],
+          program.programId
+        );
+      await program.methods
+        .openReferralRewards(SIGNER.publicKey)
+        .accounts({ mint: NATIVE_MINT })
+        .rpc({ commitment: "confirmed" });
+
+      await program.methods
+        .claimWinnings(gameIndex)
+        .accounts({
+          opponentProfile: profilePda(mockPlayerBKeypair.publicKey),
+          opponentReferralRewards: solReferralRewardsPda,
+          ...solAccounts(mockPlayerAKeypair.publicKey),
+          authority: mockPlayerAKeypair.publicKey,
+          pricesAccount: pricesPda,
+        })
+        .signers([mockPlayerAKeypair])
+        .rpc({ commitment: "confirmed" });
+
+      const hostBalanceAfter = await connection.getBalance(
+        mockPlayerAKeypair.publicKey
+      );
+      expect(await connection.getBalance(solEscrowPda)).to.equal(
+        escrowBalanceBefore
+      );
+      // Less the transaction fee
+      expect(hostBalanceAfter - hostBalanceBefore).to.be.closeTo(
+        SOL_BET_SIZE * 2,
+        LAMPORTS_PER_SOL / 1000
+      );
+    });
+
+    it("reconciles the SOL escrow against locked stakes", async () => {
+      const report = await program.methods
+        .reconcileVault()
+        .accounts({
+          mint: NATIVE_MINT,
+          vaultAccount: null,
+          solEscrow: solEscrowPda,
+        })
+        .view();
+
+      expect(report.lockedAmount.toNumber()).to.equal(0);
+      expect(report.deficit.toNumber()).to.equal(0);
+    });
+  });
 });